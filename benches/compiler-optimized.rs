@@ -75,5 +75,3 @@ mod benches {
         })
     }
 }
-
-fn main() {}
@@ -1,8 +1,10 @@
 //! Contains helper utilities for parsing items that have been annotated with the `enum_dispatch`
 //! procedural macro attribute.
 use crate::proc_macro;
-use syn;
 use crate::enum_dispatch_item;
+use crate::impl_block::ImplBlock;
+
+use syn::parse::Parser;
 
 /// Enumerates all successful results of parsing an `enum_dispatch` annotated syntax block.
 #[derive(Clone)]
@@ -12,15 +14,21 @@ pub enum ParsedItem {
 }
 
 /// Parses any syntax item that was annotated with the `enum_dispatch` attribute and returns its
-/// itemized results.
-pub fn parse_attributed(item: proc_macro::TokenStream) -> Result<ParsedItem, ()> {
-    match syn::parse(item.clone()) {
-        Ok(enumdef) => return Ok(ParsedItem::EnumDispatch(enumdef)),
-        Err(_) => (),
-    };
-    match syn::parse(item) {
-        Ok(traitdef) => return Ok(ParsedItem::Trait(traitdef)),
-        Err(_) => (),
+/// itemized results. Signature-only impl blocks are returned as the hidden traits they're cached
+/// as. If the item is neither a trait nor an impl block, the error from parsing it as an enum is
+/// returned.
+pub fn parse_attributed(item: proc_macro::TokenStream) -> syn::parse::Result<ParsedItem> {
+    if let Ok(traitdef) = syn::parse(item.clone()) {
+        return Ok(ParsedItem::Trait(traitdef));
+    }
+    let is_impl = |input: syn::parse::ParseStream| {
+        input.call(syn::Attribute::parse_outer)?;
+        let is_impl = input.peek(syn::Token![impl]);
+        input.parse::<proc_macro2::TokenStream>()?;
+        Ok(is_impl)
     };
-    Err(())
+    if is_impl.parse(item.clone())? {
+        return syn::parse::<ImplBlock>(item).map(|block| ParsedItem::Trait(block.to_cached()));
+    }
+    syn::parse(item).map(ParsedItem::EnumDispatch)
 }
@@ -7,9 +7,7 @@
 //! statically. Unfortunately, doing so strips any related `Span` information, preventing error
 //! messages from being as informative as they could be. For now, it seems this is the best option
 //! available.
-use proc_macro::Ident;
 use quote::ToTokens;
-use syn;
 
 use lazy_static::lazy_static;
 
@@ -17,6 +15,7 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 
 use crate::enum_dispatch_item;
+use crate::std_traits;
 
 // Magical storage for trait definitions so that they can be used when parsing other syntax
 // structures.
@@ -44,54 +43,73 @@ pub fn cache_enum_dispatch(item: enum_dispatch_item::EnumDispatchItem) {
         .insert(identname, item.into_token_stream().to_string());
 }
 
+/// Store the definition of a trait declared by `remote_trait!`, under the path used to link to it.
+pub fn cache_remote_trait(key: String, item: syn::ItemTrait) {
+    TRAIT_DEFS
+        .lock()
+        .unwrap()
+        .insert(key, item.into_token_stream().to_string());
+}
+
+/// Returns the cached definition of the named trait, if it has been registered. Otherwise, traits
+/// from the built-in catalog of standard library traits are returned.
+pub fn cached_trait(defname: &str) -> Option<syn::ItemTrait> {
+    TRAIT_DEFS
+        .lock()
+        .unwrap()
+        .get(defname)
+        .map(|entry| syn::parse(entry.parse().unwrap()).unwrap())
+        .or_else(|| std_traits::std_trait(defname))
+}
+
+/// Converts a path to the form used to key definitions in the cache, like `some_crate::Handler`.
+/// Leading colons and generic arguments are ignored.
+pub fn path_key(path: &syn::Path) -> String {
+    path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::")
+}
+
 /// Cache a "link" to be fulfilled once the needed definition is also cached.
-pub fn defer_link(needed: &Ident, cached: &::proc_macro2::Ident) {
-    // cached is a proc_macro2::Ident until there is a good way to convert into proc_macro::Ident.
+pub fn defer_link(needed: &str, cached: &str) {
     let (needed, cached) = (needed.to_string(), cached.to_string());
     let mut deferred_links = DEFERRED_LINKS.lock().unwrap();
-    if deferred_links.contains_key(&needed) {
-        deferred_links.get_mut(&needed).unwrap().push(cached.to_owned());
-    } else {
-        deferred_links.insert(needed.to_owned(), vec![cached.to_owned()]);
-    }
-    if deferred_links.contains_key(&cached) {
-        deferred_links.get_mut(&cached).unwrap().push(needed);
-    } else {
-        deferred_links.insert(cached, vec![needed]);
-    }
+    deferred_links.entry(needed.to_owned()).or_default().push(cached.to_owned());
+    deferred_links.entry(cached).or_default().push(needed);
 }
 
 /// Returns a list of all of the trait definitions that were previously linked to the supplied enum
-/// name.
-pub fn fulfilled_by_enum(defname: &::proc_macro2::Ident) -> Vec<syn::ItemTrait> {
-    let idents = match DEFERRED_LINKS.lock().unwrap().remove_entry(&defname.to_string()) {
+/// name, along with the names they were linked by.
+pub fn fulfilled_by_enum(defname: &str) -> Vec<(String, syn::ItemTrait)> {
+    let idents = match DEFERRED_LINKS.lock().unwrap().remove_entry(defname) {
         Some((_, links)) => links,
         None => vec![],
     };
-    idents.iter().filter_map(|ident_string| {
-        match TRAIT_DEFS.lock().unwrap().get(ident_string) {
-            Some(entry) => Some(syn::parse(entry.parse().unwrap()).unwrap()),
-            None => None,
-        }
-    }).collect()
+    idents
+        .into_iter()
+        .filter_map(|ident_string| {
+            let traitdef = cached_trait(&ident_string)?;
+            Some((ident_string, traitdef))
+        })
+        .collect()
 }
 
 /// Returns a list of all of the enum definitions that were previously linked to the supplied trait
 /// name.
-pub fn fulfilled_by_trait(defname: &::proc_macro2::Ident) -> Vec<enum_dispatch_item::EnumDispatchItem> {
-    let idents = match DEFERRED_LINKS.lock().unwrap().remove_entry(&defname.to_string()) {
+pub fn fulfilled_by_trait(defname: &str) -> Vec<enum_dispatch_item::EnumDispatchItem> {
+    let idents = match DEFERRED_LINKS.lock().unwrap().remove_entry(defname) {
         Some((_, links)) => links,
         None => vec![],
     };
     idents.iter().filter_map(|ident_string| {
-        match ENUM_DEFS.lock().unwrap().get(ident_string) {
-            Some(entry) => Some(syn::parse(entry.parse().unwrap()).unwrap()),
-            None => None,
-        }
+        ENUM_DEFS.lock().unwrap().get(ident_string).map(|entry| syn::parse(entry.parse().unwrap()).unwrap())
     }).collect()
 }
 
-pub fn remove_entry(defname: &::proc_macro2::Ident) {
-    DEFERRED_LINKS.lock().unwrap().remove_entry(&defname.to_string());
+/// Returns the cached definition of the named enum, if it has been registered.
+pub fn cached_enum(defname: &str) -> Option<enum_dispatch_item::EnumDispatchItem> {
+    ENUM_DEFS.lock().unwrap().get(defname).map(|entry| syn::parse(entry.parse().unwrap()).unwrap())
+}
+
+pub fn remove_entry(defname: &str) {
+    DEFERRED_LINKS.lock().unwrap().remove_entry(defname);
 }
 
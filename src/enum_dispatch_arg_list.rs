@@ -0,0 +1,164 @@
+//! Provides a parser for the arguments passed to `enum_dispatch` attributes, such as
+//! `#[enum_dispatch(KnobControl)]` on an enum or `#[enum_dispatch(from_default)]` on one of its
+//! variants.
+//!
+//! Arguments are separated by commas, and each one takes one of four forms:
+//!
+//! - a single identifier, like `KnobControl` or `from_default`
+//! - a path, like `some_crate::Handler`, naming a trait registered with `remote_trait!`
+//! - an identifier with a value, like `box_above = 128` or `fallback = path::to::function`
+//! - an identifier with a parenthesized list, like `serde(tag = "type")`
+//!
+//! Values are kept as raw tokens, since what they should be parsed as depends on the argument.
+//! They can't contain commas outside of a delimited group.
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+
+use crate::cache;
+
+/// A single argument to an `enum_dispatch` attribute.
+#[derive(Clone)]
+pub enum EnumDispatchArg {
+    /// `name`
+    Flag(syn::Ident),
+    /// `path::to::name`
+    Path(syn::Path),
+    /// `name = value`
+    Value(syn::Ident, TokenStream),
+    /// `name(tokens)`
+    List(syn::Ident, TokenStream),
+}
+
+impl EnumDispatchArg {
+    /// Returns the identifier at the beginning of the argument.
+    pub fn name(&self) -> &syn::Ident {
+        match self {
+            EnumDispatchArg::Flag(name) => name,
+            EnumDispatchArg::Path(path) => &path.segments.iter().last().unwrap().ident,
+            EnumDispatchArg::Value(name, _) => name,
+            EnumDispatchArg::List(name, _) => name,
+        }
+    }
+
+    /// Parses the value of a `name = value` argument as the specified syntax type, returning an
+    /// error if the argument is of a different form.
+    pub fn parse_value<T: syn::parse::Parse>(&self) -> syn::parse::Result<T> {
+        match self {
+            EnumDispatchArg::Value(_, value) => syn::parse2(value.clone()),
+            _ => Err(syn::Error::new(
+                self.name().span(),
+                format!("Expected a value for the `{}` enum_dispatch argument, like `{} = ...`", self.name(), self.name()),
+            )),
+        }
+    }
+
+    /// Returns an error if the argument is not a plain `name` flag.
+    pub fn expect_flag(&self) -> syn::parse::Result<()> {
+        match self {
+            EnumDispatchArg::Flag(_) => Ok(()),
+            _ => Err(syn::Error::new(
+                self.name().span(),
+                format!("The `{}` enum_dispatch argument doesn't take a value", self.name()),
+            )),
+        }
+    }
+
+    /// Returns the name of the definition that the argument links to, which is how the definition
+    /// is keyed in the cache. Returns an error if the argument can't be a link.
+    pub fn link_name(&self) -> syn::parse::Result<String> {
+        match self {
+            EnumDispatchArg::Path(path) => Ok(cache::path_key(path)),
+            _ => self.expect_flag().map(|()| self.name().to_string()),
+        }
+    }
+
+    /// Creates an error describing the argument as unsupported in the given context.
+    pub fn unsupported(&self, context: &str) -> syn::Error {
+        syn::Error::new(
+            self.name().span(),
+            format!("Unsupported enum_dispatch argument `{}` {}", self.name(), context),
+        )
+    }
+}
+
+impl syn::parse::Parse for EnumDispatchArg {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        if input.peek(syn::Token![::]) || input.peek2(syn::Token![::]) {
+            return input.call(syn::Path::parse_mod_style).map(EnumDispatchArg::Path);
+        }
+        let name: syn::Ident = input.parse()?;
+        if input.peek(syn::Token![=]) {
+            input.parse::<syn::Token![=]>()?;
+            let mut value = TokenStream::new();
+            while !input.is_empty() && !input.peek(syn::Token![,]) {
+                value.extend(Some(input.parse::<proc_macro2::TokenTree>()?));
+            }
+            if value.is_empty() {
+                return Err(input.error("Expected a value after `=`"));
+            }
+            Ok(EnumDispatchArg::Value(name, value))
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            Ok(EnumDispatchArg::List(name, content.parse()?))
+        } else {
+            Ok(EnumDispatchArg::Flag(name))
+        }
+    }
+}
+
+impl ToTokens for EnumDispatchArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            EnumDispatchArg::Flag(name) => name.to_tokens(tokens),
+            EnumDispatchArg::Path(path) => path.to_tokens(tokens),
+            EnumDispatchArg::Value(name, value) => {
+                name.to_tokens(tokens);
+                syn::token::Eq::default().to_tokens(tokens);
+                value.to_tokens(tokens);
+            }
+            EnumDispatchArg::List(name, list) => {
+                name.to_tokens(tokens);
+                syn::token::Paren::default().surround(tokens, |tokens| list.to_tokens(tokens));
+            }
+        }
+    }
+}
+
+/// A comma-separated list of `EnumDispatchArg`s.
+#[derive(Clone, Default)]
+pub struct EnumDispatchArgList {
+    pub args: syn::punctuated::Punctuated<EnumDispatchArg, syn::token::Comma>,
+}
+
+impl syn::parse::Parse for EnumDispatchArgList {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        Ok(EnumDispatchArgList {
+            args: input.parse_terminated(EnumDispatchArg::parse)?,
+        })
+    }
+}
+
+impl EnumDispatchArgList {
+    /// Parses the arguments of an `#[enum_dispatch(...)]` attribute. An attribute without any
+    /// arguments produces an empty list.
+    pub fn from_attr(attr: &syn::Attribute) -> syn::parse::Result<Self> {
+        let mut tts = attr.tts.clone().into_iter();
+        match (tts.next(), tts.next()) {
+            (None, _) => Ok(Self::default()),
+            (Some(proc_macro2::TokenTree::Group(ref group)), None)
+                if group.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+            {
+                syn::parse2(group.stream())
+            }
+            _ => Err(syn::Error::new(attr.span(), "Expected enum_dispatch arguments in parentheses")),
+        }
+    }
+}
+
+/// Checks whether an attribute is an `enum_dispatch` attribute, which may be written as either
+/// `#[enum_dispatch]` or `#[enum_dispatch::enum_dispatch]`.
+pub fn is_enum_dispatch_attr(attr: &syn::Attribute) -> bool {
+    matches!(attr.path.segments.iter().last(), Some(segment) if segment.ident == "enum_dispatch")
+}
@@ -6,12 +6,18 @@
 //! specified as a `syn::Type` rather than a `syn::Variant`. In the case of basic unit fields named
 //! after existing scoped types, a normal Rust enum can be parsed as an EnumDispatchItem without
 //! issue.
-use syn;
-use quote::TokenStreamExt;
-use proc_macro2;
+use quote::{ToTokens, TokenStreamExt};
+use syn::spanned::Spanned;
 
+use crate::cache;
+use crate::enum_dispatch_arg_list::{is_enum_dispatch_attr, EnumDispatchArg, EnumDispatchArgList};
 use crate::enum_dispatch_variant::EnumDispatchVariant;
+use crate::name_registry::FromName;
+use crate::partial_dispatch::parse_trait_list;
+use crate::serde_repr::SerdeRepr;
+use crate::shared_fields::{parse_shared_fields, SharedField};
 use crate::filter_attrs::FilterAttrs;
+use crate::variant_storage::stored_type;
 
 /// A structure that can be used to store syntax information about an `enum_dispatch` enum.
 ///
@@ -25,11 +31,269 @@ pub struct EnumDispatchItem {
     pub generics: syn::Generics,
     brace_token: syn::token::Brace,
     pub variants: syn::punctuated::Punctuated<EnumDispatchVariant, syn::token::Comma>,
+    /// Boxed to keep parsed enums from being much larger than parsed traits.
+    pub options: Box<EnumOptions>,
+}
+
+/// Enum-wide options, specified alongside the linked trait names in `#[enum_dispatch(...)]`
+/// attributes on the enum.
+#[derive(Clone, Default)]
+pub struct EnumOptions {
+    /// `box_above = N`: store the payloads of variants larger than `N` bytes in a `Box`.
+    pub box_above: Option<syn::LitInt>,
+    /// `max_size = N`: fail to compile if the enum is larger than `N` bytes.
+    pub max_size: Option<syn::LitInt>,
+    /// `no_from`: don't generate any `From` impls for the enum.
+    pub no_from: bool,
+    /// `accessors`: generate `is_*`, `as_*`, `as_*_mut`, and `into_*` methods for each variant.
+    pub accessors: bool,
+    /// `try_from`: implement `TryFrom<Enum>` for each distinct payload type.
+    pub try_from: bool,
+    /// `dyn_conversions`: convert the enum into trait objects of its object-safe linked traits,
+    /// with `From` impls and the `as_dyn`, `as_dyn_mut`, and `into_box_dyn` methods.
+    pub dyn_conversions: bool,
+    /// `any_conversions`: generate `try_from_any` and `try_from_any_ref`, converting type-erased
+    /// values back into the enum.
+    pub any_conversions: bool,
+    /// `introspection`: generate methods and constants describing the variants.
+    pub introspection: bool,
+    /// `kind = Name`: generate a fieldless enum with the given name, mirroring the variants.
+    pub kind: Option<syn::Ident>,
+    /// `variant_trait = Name`: generate a trait with the given name, implemented by the payload
+    /// types, along with methods accessing the payload by type.
+    pub variant_trait: Option<syn::Ident>,
+    /// `visitor = Name`: generate visitor traits with the given name, along with the `accept`
+    /// methods and the `match` macro.
+    pub visitor: Option<syn::Ident>,
+    /// `pinned`: generate the guards needed to project `Pin<&mut Self>` receivers onto the
+    /// payloads. Set automatically for traits with such methods known when the enum is defined.
+    pub pinned: bool,
+    /// `serde(...)`: implement `Serialize` and `Deserialize` using the given representation.
+    pub serde: Option<SerdeRepr>,
+    /// `json_schema`: implement `schemars::JsonSchema`.
+    pub json_schema: bool,
+    /// `from_name`: generate methods creating variants from their names.
+    pub from_name: Option<FromName>,
+    /// `superset_of = Enum`: convert to and from an enum whose variants this enum also has.
+    pub superset_of: Option<syn::Path>,
+    /// `partial`: the traits linked alongside it are only implemented by the variants declaring
+    /// them with `implements(...)`. Cached as `partial(Trait, ...)`.
+    pub partial: Vec<syn::Path>,
+    /// `inherent`: the traits linked alongside it also get inherent methods on the enum, so that
+    /// they can be called without importing the trait. Cached as `inherent(Trait, ...)`.
+    pub inherent: Vec<syn::Path>,
+    /// `fields(name: Type, ...)`: generate accessors for fields shared by every payload.
+    pub fields: Vec<SharedField>,
+}
+
+impl EnumOptions {
+    /// Applies a single argument of an enum's `enum_dispatch` attribute. Returns `false` if the
+    /// argument isn't an option, in which case it names a trait to link the enum to.
+    pub fn apply(&mut self, arg: &EnumDispatchArg) -> syn::parse::Result<bool> {
+        if let EnumDispatchArg::Path(_) = arg {
+            return Ok(false);
+        }
+        match arg.name().to_string().as_str() {
+            "box_above" => self.box_above = Some(arg.parse_value()?),
+            "max_size" => self.max_size = Some(arg.parse_value()?),
+            "kind" => self.kind = Some(arg.parse_value()?),
+            "variant_trait" => self.variant_trait = Some(arg.parse_value()?),
+            "visitor" => self.visitor = Some(arg.parse_value()?),
+            "serde" => self.serde = Some(SerdeRepr::from_arg(arg)?),
+            "from_name" => self.from_name = Some(FromName::from_arg(arg)?),
+            "superset_of" => self.superset_of = Some(arg.parse_value()?),
+            "fields" => match arg {
+                EnumDispatchArg::List(_, list) => self.fields.extend(parse_shared_fields(list)?),
+                _ => return Err(syn::Error::new(arg.name().span(), "Expected `fields(name: Type, ...)`")),
+            },
+            "no_from" => {
+                arg.expect_flag()?;
+                self.no_from = true;
+            }
+            "accessors" => {
+                arg.expect_flag()?;
+                self.accessors = true;
+            }
+            "try_from" => {
+                arg.expect_flag()?;
+                self.try_from = true;
+            }
+            "dyn_conversions" => {
+                arg.expect_flag()?;
+                self.dyn_conversions = true;
+            }
+            "any_conversions" => {
+                arg.expect_flag()?;
+                self.any_conversions = true;
+            }
+            "pinned" => {
+                arg.expect_flag()?;
+                self.pinned = true;
+            }
+            "introspection" => {
+                arg.expect_flag()?;
+                self.introspection = true;
+            }
+            // The bare flags apply to the traits linked in the same attribute, which are added
+            // by `mark_links`.
+            "partial" => {
+                if let EnumDispatchArg::List(_, list) = arg {
+                    self.partial.extend(parse_trait_list(list)?);
+                }
+            }
+            "inherent" => {
+                if let EnumDispatchArg::List(_, list) = arg {
+                    self.inherent.extend(parse_trait_list(list)?);
+                }
+            }
+            "json_schema" => {
+                arg.expect_flag()?;
+                if !cfg!(feature = "schemars") {
+                    return Err(syn::Error::new(
+                        arg.name().span(),
+                        "The `json_schema` enum_dispatch option requires the `schemars` feature of enum_dispatch",
+                    ));
+                }
+                self.json_schema = true;
+            }
+            // Trait names are CamelCase, so anything else is taken for a misspelled option
+            // rather than silently linking a trait that doesn't exist.
+            name => {
+                let is_trait_name = matches!(name.chars().next(), Some(first) if first.is_uppercase());
+                return match arg {
+                    EnumDispatchArg::Flag(_) if is_trait_name => Ok(false),
+                    _ => Err(syn::Error::new(arg.name().span(), format!("Unknown enum_dispatch option `{}`", name))),
+                };
+            }
+        }
+        Ok(true)
+    }
+
+    /// Applies the `partial` and `inherent` flags of an attribute to the traits linked by `links`,
+    /// the attribute's remaining arguments.
+    pub fn mark_links(&mut self, args: &EnumDispatchArgList, links: &[EnumDispatchArg]) {
+        let has_flag = |flag: &str| args.args.iter().any(|arg| matches!(arg, EnumDispatchArg::Flag(name) if name == flag));
+        let paths = links.iter().map(|link| match link {
+            EnumDispatchArg::Path(path) => path.clone(),
+            _ => link.name().clone().into(),
+        });
+        if has_flag("partial") {
+            self.partial.extend(paths.clone());
+        }
+        if has_flag("inherent") {
+            self.inherent.extend(paths);
+        }
+    }
+
+    /// Checks whether the trait with the given cache key is linked partially.
+    pub fn is_partial(&self, key: &str) -> bool {
+        self.partial.iter().any(|path| cache::path_key(path) == key)
+    }
+
+    /// Checks whether the trait with the given cache key gets inherent methods.
+    pub fn is_inherent(&self, key: &str) -> bool {
+        self.inherent.iter().any(|path| cache::path_key(path) == key)
+    }
+
+    /// Collects the options specified by any `enum_dispatch` attributes in the given list,
+    /// ignoring the names of linked traits.
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::parse::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|attr| is_enum_dispatch_attr(attr)) {
+            for arg in EnumDispatchArgList::from_attr(attr)?.args.iter() {
+                options.apply(arg)?;
+            }
+        }
+        Ok(options)
+    }
+
+    /// Converts the options back into attribute arguments, so that they survive being cached.
+    fn to_args(&self) -> Vec<proc_macro2::TokenStream> {
+        let mut args = vec![];
+        if let Some(box_above) = &self.box_above {
+            args.push(quote::quote! { box_above = #box_above });
+        }
+        if let Some(max_size) = &self.max_size {
+            args.push(quote::quote! { max_size = #max_size });
+        }
+        if self.no_from {
+            args.push(quote::quote! { no_from });
+        }
+        if self.accessors {
+            args.push(quote::quote! { accessors });
+        }
+        if self.try_from {
+            args.push(quote::quote! { try_from });
+        }
+        if self.dyn_conversions {
+            args.push(quote::quote! { dyn_conversions });
+        }
+        if self.any_conversions {
+            args.push(quote::quote! { any_conversions });
+        }
+        if self.introspection {
+            args.push(quote::quote! { introspection });
+        }
+        if let Some(kind) = &self.kind {
+            args.push(quote::quote! { kind = #kind });
+        }
+        if let Some(variant_trait) = &self.variant_trait {
+            args.push(quote::quote! { variant_trait = #variant_trait });
+        }
+        if let Some(visitor) = &self.visitor {
+            args.push(quote::quote! { visitor = #visitor });
+        }
+        if self.pinned {
+            args.push(quote::quote! { pinned });
+        }
+        if let Some(serde) = &self.serde {
+            args.push(quote::quote! { #serde });
+        }
+        if self.json_schema {
+            args.push(quote::quote! { json_schema });
+        }
+        if let Some(from_name) = &self.from_name {
+            args.push(quote::quote! { #from_name });
+        }
+        if let Some(superset_of) = &self.superset_of {
+            args.push(quote::quote! { superset_of = #superset_of });
+        }
+        if !self.partial.is_empty() {
+            let partial = &self.partial;
+            args.push(quote::quote! { partial(#(#partial),*) });
+        }
+        if !self.inherent.is_empty() {
+            let inherent = &self.inherent;
+            args.push(quote::quote! { inherent(#(#inherent),*) });
+        }
+        if !self.fields.is_empty() {
+            let fields = &self.fields;
+            args.push(quote::quote! { fields(#(#fields),*) });
+        }
+        args
+    }
 }
 
 /// Allows `EnumDispatchItem`s to be parsed from `String`s or `TokenStream`s.
 impl syn::parse::Parse for EnumDispatchItem {
     fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        Self::parse_with_variants(input, EnumDispatchVariant::parse)
+    }
+}
+
+impl EnumDispatchItem {
+    /// Parses an `EnumDispatchItem` whose variants may be specified as arbitrary types, as
+    /// accepted by the function-like `enum_dispatch_enum!` macro.
+    pub fn parse_type_shorthand(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        Self::parse_with_variants(input, EnumDispatchVariant::parse_type_shorthand)
+    }
+
+    /// Parses an `EnumDispatchItem`, using the provided function to parse each of its variants.
+    /// Variants are checked for name collisions, since names may have been derived automatically.
+    fn parse_with_variants(
+        input: syn::parse::ParseStream,
+        parse_variant: fn(syn::parse::ParseStream) -> syn::parse::Result<EnumDispatchVariant>,
+    ) -> syn::parse::Result<Self> {
         let attrs = input.call(syn::Attribute::parse_outer)?;
         let vis: syn::Visibility = input.parse()?;
         let enum_token = input.parse::<syn::Token![enum]>()?;
@@ -38,26 +302,58 @@ impl syn::parse::Parse for EnumDispatchItem {
         let where_clause = input.parse()?;
         let content;
         let brace_token = syn::braced!(content in input);
-        let variants = content.parse_terminated(EnumDispatchVariant::parse)?;
+        let variants = content.parse_terminated(parse_variant)?;
+        check_variant_collisions(&variants)?;
+        let options = Box::new(EnumOptions::from_attrs(&attrs)?);
         Ok(Self {
             attrs,
             vis,
             enum_token,
             ident,
             generics: syn::Generics {
-                where_clause: where_clause,
+                where_clause,
                 ..generics
             },
             brace_token,
-            variants
+            variants,
+            options,
         })
     }
 }
 
+/// Returns an error if two variants share the same name.
+fn check_variant_collisions(
+    variants: &syn::punctuated::Punctuated<EnumDispatchVariant, syn::token::Comma>,
+) -> syn::parse::Result<()> {
+    for (i, variant) in variants.iter().enumerate() {
+        if let Some(previous) = variants.iter().take(i).find(|previous| previous.ident == variant.ident) {
+            let describe = |variant: &EnumDispatchVariant| match &variant.ty {
+                Some(ty) => format!("`{}`", ty.into_token_stream()),
+                None => format!("unit variant `{}`", variant.ident),
+            };
+            let span = variant.ty.as_ref().map_or(variant.ident.span(), |ty| ty.span());
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "enum_dispatch variant name `{}` for {} collides with the variant for {}; use the `Name(Type)` form to rename one of them",
+                    variant.ident,
+                    describe(variant),
+                    describe(previous)
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Allows `EnumDispatchItem`s to be converted into `TokenStream`s.
 impl syn::export::quote::ToTokens for EnumDispatchItem {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         tokens.append_all(self.attrs.outer());
+        let options = self.options.to_args();
+        if !options.is_empty() {
+            tokens.extend(quote::quote! { #[enum_dispatch(#(#options),*)] });
+        }
         self.vis.to_tokens(tokens);
         self.enum_token.to_tokens(tokens);
         self.ident.to_tokens(tokens);
@@ -74,27 +370,18 @@ impl syn::export::quote::ToTokens for EnumDispatchItem {
 impl ::std::convert::From<EnumDispatchItem> for syn::ItemEnum {
     fn from(item: EnumDispatchItem) -> syn::ItemEnum {
         use ::std::iter::FromIterator;
-        let variants: Vec<syn::Variant> = item.variants.iter().map(|variant: &EnumDispatchVariant| {
-            syn::Variant {
-                attrs: vec![],
-                ident: variant.ident.to_owned(),
-                fields: syn::Fields::Unnamed(syn::FieldsUnnamed {
-                    paren_token: Default::default(),
-                    unnamed: {
-                        let mut punctuated = syn::punctuated::Punctuated::new();
-                        punctuated.push(syn::Field {
-                            attrs: vec![],
-                            vis: syn::Visibility::Inherited,
-                            ident: None,
-                            colon_token: Default::default(),
-                            ty: variant.ty.to_owned(),
-                        });
-                        punctuated
-                    },
-                }),
-                discriminant: None,
-            }
-        }).collect();
+        let variants: Vec<syn::Variant> = item
+            .variants
+            .iter()
+            .map(|variant| {
+                let stored_type = stored_type(&item, variant);
+                let mut expanded = syn::Variant::from(variant.clone());
+                if let (Some(stored_type), Some(payload_field)) = (stored_type, variant.payload_field) {
+                    expanded.fields.iter_mut().nth(payload_field).unwrap().ty = stored_type;
+                }
+                expanded
+            })
+            .collect();
         syn::ItemEnum {
             attrs: item.attrs,
             vis: item.vis,
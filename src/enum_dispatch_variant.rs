@@ -4,21 +4,145 @@
 //! Each variant can be either just a type, or a name with a single associated tuple type
 //! parameter. In the first form, the name is simply the same as the type. In the second, the name
 //! is explicitly specified.
+//!
+//! Within the function-like `enum_dispatch_enum!` macro, the first form may be any type rather
+//! than just an identifier. In that case, a name is derived from the type instead.
+//!
+//! Named variants may also have several fields, or use braces for struct-like fields. In that
+//! case, the field that trait methods should be dispatched to must be marked with a `#[dispatch]`
+//! attribute.
+//!
+//! Variants with an `#[enum_dispatch(fallback ...)]` attribute don't dispatch to their fields at
+//! all, so they may also be unit variants.
 
 use std::iter::FromIterator;
 
-use syn;
-use quote::TokenStreamExt;
-use proc_macro2;
+use quote::{ToTokens, TokenStreamExt};
+use syn::spanned::Spanned;
 
+use crate::enum_dispatch_arg_list::{is_enum_dispatch_attr, EnumDispatchArg, EnumDispatchArgList};
 use crate::filter_attrs::FilterAttrs;
+use crate::partial_dispatch::parse_trait_list;
+use crate::payload_wrapper::LockStrategy;
 
 /// A structure that can be used to store syntax information about an `enum_dispatch` enum variant.
 #[derive(Clone)]
 pub struct EnumDispatchVariant {
     pub attrs: Vec<syn::Attribute>,
     pub ident: syn::Ident,
-    pub ty: syn::Type,
+    /// The type of the variant's payload, which trait methods are dispatched to unless the variant
+    /// has a fallback. Unit variants have no payload.
+    pub ty: Option<syn::Type>,
+    /// All fields of the variant. For variants specified as just a type, this is a single unnamed
+    /// field of that type.
+    pub fields: syn::Fields,
+    /// The position of the payload field within `fields`.
+    pub payload_field: Option<usize>,
+    pub options: VariantOptions,
+}
+
+/// Per-variant options, specified using `#[enum_dispatch(...)]` attributes on the variant.
+#[derive(Clone, Default)]
+pub struct VariantOptions {
+    /// Generate a `From` impl for variants with several fields, filling in the fields that aren't
+    /// dispatched to using `Default::default()`.
+    pub from_default: bool,
+    /// What to do instead of dispatching trait methods to the variant's payload.
+    pub fallback: Option<Fallback>,
+    /// How to acquire locks wrapping the payload, like `Mutex` or `RwLock`. Locks are only
+    /// acquired if this is set.
+    pub lock: Option<LockStrategy>,
+    /// Dispatch to the payload as-is, without looking through smart pointers or locks.
+    pub no_unwrap: bool,
+    /// The payload is another `enum_dispatch` enum, whose conversions and variants are flattened
+    /// into this one.
+    pub flatten: bool,
+    /// Don't generate any `From` impls for the variant.
+    pub no_from: bool,
+    /// `rename = "..."`: the name of the variant in the enum's serde representation.
+    pub rename: Option<syn::LitStr>,
+    /// `constructor = path::to::function`: create the payload from the arguments of the enum's
+    /// `from_name_with` method.
+    pub constructor: Option<syn::Path>,
+    /// `implements(Trait, ...)`: the traits linked with `partial` that the payload implements.
+    pub implements: Vec<syn::Path>,
+}
+
+/// Describes how trait methods are implemented for a variant that doesn't dispatch to a payload,
+/// such as a unit variant.
+#[derive(Clone)]
+pub enum Fallback {
+    /// `fallback = path::to::function`: call the function with the same arguments as the trait
+    /// method, including the receiver.
+    Function(syn::Path),
+    /// `fallback(default_method)`: run the trait's default method body.
+    DefaultMethod,
+    /// `fallback(default_value)`: return `Default::default()`.
+    DefaultValue,
+}
+
+impl VariantOptions {
+    /// Collects the options specified by any `enum_dispatch` attributes in the given list.
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::parse::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|attr| is_enum_dispatch_attr(attr)) {
+            for arg in EnumDispatchArgList::from_attr(attr)?.args {
+                match arg.name().to_string().as_str() {
+                    "from_default" => {
+                        arg.expect_flag()?;
+                        options.from_default = true;
+                    }
+                    "fallback" => options.fallback = Some(Fallback::from_arg(&arg)?),
+                    "lock" => match &arg {
+                        EnumDispatchArg::Flag(_) => options.lock = Some(LockStrategy::default()),
+                        _ => options.lock = Some(arg.parse_value()?),
+                    },
+                    "no_unwrap" => {
+                        arg.expect_flag()?;
+                        options.no_unwrap = true;
+                    }
+                    "flatten" => {
+                        arg.expect_flag()?;
+                        options.flatten = true;
+                    }
+                    "rename" => options.rename = Some(arg.parse_value()?),
+                    "constructor" => options.constructor = Some(arg.parse_value()?),
+                    "implements" => match &arg {
+                        EnumDispatchArg::List(_, list) => options.implements.extend(parse_trait_list(list)?),
+                        _ => return Err(syn::Error::new(arg.name().span(), "Expected `implements(Trait, ...)`")),
+                    },
+                    "no_from" => {
+                        arg.expect_flag()?;
+                        options.no_from = true;
+                    }
+                    _ => return Err(arg.unsupported("on an enum variant")),
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl Fallback {
+    /// Parses a fallback from either the `fallback = path` or `fallback(strategy)` form.
+    fn from_arg(arg: &EnumDispatchArg) -> syn::parse::Result<Self> {
+        match arg {
+            EnumDispatchArg::List(_, strategy) => {
+                let strategy: syn::Ident = syn::parse2(strategy.clone())?;
+                if strategy == "default_method" {
+                    Ok(Fallback::DefaultMethod)
+                } else if strategy == "default_value" {
+                    Ok(Fallback::DefaultValue)
+                } else {
+                    Err(syn::Error::new(
+                        strategy.span(),
+                        "Expected `default_method` or `default_value` as the enum_dispatch fallback strategy",
+                    ))
+                }
+            }
+            _ => Ok(Fallback::Function(arg.parse_value()?)),
+        }
+    }
 }
 
 /// Allows `EnumDispatchItem`s to be parsed from `String`s or `TokenStream`s.
@@ -26,23 +150,136 @@ impl syn::parse::Parse for EnumDispatchVariant {
     fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
         let attrs = input.call(syn::Attribute::parse_outer)?;
         let ident: syn::Ident = input.parse()?;
-        let ty = if input.peek(syn::token::Brace) {
-            unimplemented!("enum_dispatch variants cannot have braces for arguments");
+        let fields = if input.peek(syn::token::Brace) {
+            syn::Fields::Named(input.parse()?)
         } else if input.peek(syn::token::Paren) {
-            let input: syn::FieldsUnnamed = input.parse()?;
-            let mut fields = input.unnamed.iter();
-            let field_1 = fields.next().expect("Named enum_dispatch variants must have one unnamed field");
-            if fields.next().is_some() {
-                panic!("Named enum_dispatch variants can only have one unnamed field");
-            }
-            field_1.ty.clone()
+            syn::Fields::Unnamed(input.parse()?)
+        } else if VariantOptions::from_attrs(&attrs)?.fallback.is_some() {
+            // A bare identifier usually names the payload type, but variants with a fallback
+            // don't need a payload at all.
+            syn::Fields::Unit
         } else {
-            into_type(ident.clone())
+            single_field(into_type(ident.clone()))
         };
+        Self::new(attrs, ident, fields)
+    }
+}
+
+impl EnumDispatchVariant {
+    /// Creates a new variant, locating its payload field and parsing any `enum_dispatch`
+    /// attributes.
+    fn new(attrs: Vec<syn::Attribute>, ident: syn::Ident, fields: syn::Fields) -> syn::parse::Result<Self> {
+        let options = VariantOptions::from_attrs(&attrs)?;
+        let payload_field = find_payload_field(&ident, &fields, options.fallback.is_some())?;
+        let ty = payload_field.map(|index| fields.iter().nth(index).unwrap().ty.clone());
         Ok(EnumDispatchVariant {
             attrs,
             ident,
             ty,
+            fields,
+            payload_field,
+            options,
+        })
+    }
+
+    /// Parses a variant that may be specified as any type, as accepted by the function-like
+    /// `enum_dispatch_enum!` macro. Variants written in the explicitly named `Name(Type)` form are
+    /// parsed as usual, as are unit variants. Otherwise, the variant is named using
+    /// `derive_variant_name`.
+    pub fn parse_type_shorthand(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let fork = input.fork();
+        let attrs = fork.call(syn::Attribute::parse_outer)?;
+        if fork.peek(syn::Ident) && (fork.peek2(syn::token::Paren) || fork.peek2(syn::token::Brace)) {
+            return input.parse();
+        }
+        if VariantOptions::from_attrs(&attrs)?.fallback.is_some()
+            && fork.parse::<syn::Ident>().is_ok()
+            && (fork.is_empty() || fork.peek(syn::Token![,]))
+        {
+            return input.parse();
+        }
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let ty: syn::Type = input.parse()?;
+        let ident = derive_variant_name(&ty)?;
+        Self::new(attrs, ident, single_field(ty))
+    }
+
+    /// Returns `true` if trait methods are dispatched to the variant's payload, rather than being
+    /// handled by a fallback.
+    pub fn dispatches(&self) -> bool {
+        self.options.fallback.is_none()
+    }
+
+    /// Creates a pattern matching this variant of the named enum, binding the payload field to
+    /// `binding`. For variants without a payload, no fields are bound.
+    pub fn pattern(&self, enum_name: &impl ToTokens, binding: &syn::Ident) -> syn::Pat {
+        self.payload_pattern(enum_name, syn::parse_quote! { #binding })
+    }
+
+    /// Creates a pattern matching this variant of the named enum, matching the payload field
+    /// against `payload`. For variants without a payload, no fields are matched.
+    pub fn payload_pattern(&self, enum_name: &impl ToTokens, payload: syn::Pat) -> syn::Pat {
+        let variant_name = &self.ident;
+        let payload_field = match self.payload_field {
+            Some(payload_field) => payload_field,
+            None => return self.wildcard_pattern(enum_name),
+        };
+        match &self.fields {
+            syn::Fields::Named(fields) => {
+                let field_name = fields.named.iter().nth(payload_field).unwrap().ident.as_ref().unwrap();
+                if fields.named.len() == 1 {
+                    syn::parse_quote! { #enum_name::#variant_name { #field_name: #payload } }
+                } else {
+                    syn::parse_quote! { #enum_name::#variant_name { #field_name: #payload, .. } }
+                }
+            }
+            _ => {
+                let skipped = (0..payload_field).map(|_| quote::quote! { _ });
+                if self.fields.iter().count() == 1 {
+                    syn::parse_quote! { #enum_name::#variant_name(#payload) }
+                } else {
+                    syn::parse_quote! { #enum_name::#variant_name(#(#skipped,)* #payload, ..) }
+                }
+            }
+        }
+    }
+
+    /// Returns the variant's name in snake_case, for use in the names of generated methods.
+    pub fn snake_case_name(&self) -> String {
+        snake_case(&self.ident)
+    }
+
+    /// Creates a pattern matching this variant of the named enum without binding any fields.
+    pub fn wildcard_pattern(&self, enum_name: &impl ToTokens) -> syn::Pat {
+        let variant_name = &self.ident;
+        match &self.fields {
+            syn::Fields::Named(_) => syn::parse_quote! { #enum_name::#variant_name { .. } },
+            syn::Fields::Unnamed(_) => syn::parse_quote! { #enum_name::#variant_name(..) },
+            syn::Fields::Unit => syn::parse_quote! { #enum_name::#variant_name },
+        }
+    }
+
+    /// Creates an expression constructing this variant of the named enum from `value`, for use in
+    /// `From` impls. Returns `None` if the variant has other fields that can't be filled in.
+    pub fn constructor(&self, enum_name: &syn::Ident, value: &syn::Ident) -> Option<syn::Expr> {
+        let payload_field = self.payload_field?;
+        if self.fields.iter().count() > 1 && !self.options.from_default {
+            return None;
+        }
+        let variant_name = &self.ident;
+        let field_values = (0..self.fields.iter().count()).map(|i| {
+            if i == payload_field {
+                quote::quote! { #value }
+            } else {
+                quote::quote! { ::std::default::Default::default() }
+            }
+        });
+        Some(match &self.fields {
+            syn::Fields::Named(fields) => {
+                let field_names = fields.named.iter().map(|field| &field.ident);
+                syn::parse_quote! { #enum_name::#variant_name { #(#field_names: #field_values),* } }
+            }
+            _ => syn::parse_quote! { #enum_name::#variant_name(#(#field_values),*) },
         })
     }
 }
@@ -52,12 +289,76 @@ impl syn::export::quote::ToTokens for EnumDispatchVariant {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         tokens.append_all(self.attrs.outer());
         self.ident.to_tokens(tokens);
-        syn::token::Paren::default().surround(tokens, |tokens| {
-            self.ty.to_tokens(tokens);
-        });
+        self.fields.to_tokens(tokens);
+    }
+}
+
+/// Converts an `EnumDispatchVariant` into a standard Rust enum variant, removing any
+/// `enum_dispatch`-specific attributes.
+impl ::std::convert::From<EnumDispatchVariant> for syn::Variant {
+    fn from(variant: EnumDispatchVariant) -> syn::Variant {
+        let mut fields = variant.fields;
+        for field in fields.iter_mut() {
+            field.attrs.retain(|attr| !is_dispatch_attr(attr));
+        }
+        syn::Variant {
+            attrs: variant.attrs.into_iter().filter(|attr| !is_enum_dispatch_attr(attr)).collect(),
+            ident: variant.ident,
+            fields,
+            discriminant: None,
+        }
     }
 }
 
+/// Checks whether an attribute is a `#[dispatch]` marker for the dispatched field of a variant.
+fn is_dispatch_attr(attr: &syn::Attribute) -> bool {
+    attr.path.leading_colon.is_none() && attr.path.segments.len() == 1 && attr.path.segments[0].ident == "dispatch"
+}
+
+/// Determines which field of a variant holds its payload. Variants with a single field always use
+/// it, but variants with several fields must mark one of them with `#[dispatch]`. Variants with a
+/// fallback don't need a payload, so they may also have no fields or no marked field.
+fn find_payload_field(ident: &syn::Ident, fields: &syn::Fields, has_fallback: bool) -> syn::parse::Result<Option<usize>> {
+    let marked: Vec<usize> = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| field.attrs.iter().any(is_dispatch_attr))
+        .map(|(i, _)| i)
+        .collect();
+    match (fields.iter().count(), marked.as_slice()) {
+        (0, _) if has_fallback => Ok(None),
+        (0, _) => Err(syn::Error::new(
+            ident.span(),
+            "enum_dispatch variants must have at least one field, unless they specify a fallback",
+        )),
+        (1, _) => Ok(Some(0)),
+        (_, [index]) => Ok(Some(*index)),
+        (_, []) if has_fallback => Ok(None),
+        (_, []) => Err(syn::Error::new(
+            ident.span(),
+            "enum_dispatch variants with several fields must mark one of them with `#[dispatch]`",
+        )),
+        (_, _) => Err(syn::Error::new(
+            ident.span(),
+            "Only one field of an enum_dispatch variant can be marked with `#[dispatch]`",
+        )),
+    }
+}
+
+/// Creates the single unnamed field used by variants that are specified as just a type.
+fn single_field(ty: syn::Type) -> syn::Fields {
+    syn::Fields::Unnamed(syn::FieldsUnnamed {
+        paren_token: Default::default(),
+        unnamed: syn::punctuated::Punctuated::from_iter(vec![syn::Field {
+            attrs: vec![],
+            vis: syn::Visibility::Inherited,
+            ident: None,
+            colon_token: None,
+            ty,
+        }]),
+    })
+}
+
 /// When expanding shorthand `enum_dispatch` enum syntax, each specified, unnamed type variant must
 /// acquire an associated identifier to use for the name of the standard Rust enum variant.
 ///
@@ -77,3 +378,109 @@ fn into_type(ident: syn::Ident) -> syn::Type {
         qself: None
     })
 }
+
+/// Derives a deterministic variant name from an arbitrary type, for use with the function-like
+/// `enum_dispatch_enum!` macro.
+///
+/// Paths are named after their final segment, followed by the names of any generic type
+/// arguments, so `shapes::Circle` becomes `Circle` and `Wrapper<u8>` becomes `WrapperU8`.
+/// References, slices, arrays, and tuples are prefixed with `Ref`, `RefMut`, `Slice`, `Array`, or
+/// `Tuple` respectively. Other types can't be named automatically and must use an explicitly
+/// named variant instead.
+pub fn derive_variant_name(ty: &syn::Type) -> syn::parse::Result<syn::Ident> {
+    let mut name = String::new();
+    push_type_name(ty, &mut name)?;
+    Ok(syn::Ident::new(&name, ty.span()))
+}
+
+/// Appends the CamelCase name of the given type to `name`. See `derive_variant_name`.
+fn push_type_name(ty: &syn::Type, name: &mut String) -> syn::parse::Result<()> {
+    match ty {
+        syn::Type::Path(syn::TypePath { path, .. }) => {
+            let segment = path.segments.iter().last().unwrap();
+            push_camel_case(&segment.ident, name);
+            if let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments {
+                for argument in arguments.args.iter() {
+                    match argument {
+                        syn::GenericArgument::Type(ty) => push_type_name(ty, name)?,
+                        syn::GenericArgument::Binding(binding) => push_type_name(&binding.ty, name)?,
+                        syn::GenericArgument::Const(syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. })) => {
+                            name.push_str(&int.value().to_string())
+                        }
+                        syn::GenericArgument::Lifetime(_) => (),
+                        _ => return Err(unnameable(ty)),
+                    }
+                }
+            }
+        }
+        syn::Type::Reference(reference) => {
+            name.push_str(if reference.mutability.is_some() { "RefMut" } else { "Ref" });
+            push_type_name(&reference.elem, name)?;
+        }
+        syn::Type::Slice(slice) => {
+            name.push_str("Slice");
+            push_type_name(&slice.elem, name)?;
+        }
+        syn::Type::Array(array) => {
+            name.push_str("Array");
+            push_type_name(&array.elem, name)?;
+        }
+        syn::Type::Tuple(tuple) => {
+            name.push_str(if tuple.elems.is_empty() { "Unit" } else { "Tuple" });
+            for elem in tuple.elems.iter() {
+                push_type_name(elem, name)?;
+            }
+        }
+        syn::Type::Paren(paren) => push_type_name(&paren.elem, name)?,
+        syn::Type::Group(group) => push_type_name(&group.elem, name)?,
+        _ => return Err(unnameable(ty)),
+    }
+    Ok(())
+}
+
+/// Appends an identifier to `name` in CamelCase, so that `u8` becomes `U8` and `my_type` becomes
+/// `MyType`.
+fn push_camel_case(ident: &syn::Ident, name: &mut String) {
+    let ident = ident.to_string();
+    for word in ident.trim_start_matches("r#").split('_') {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.extend(chars);
+        }
+    }
+}
+
+/// Creates an error for types that can't be given an automatically derived variant name.
+fn unnameable(ty: &syn::Type) -> syn::Error {
+    syn::Error::new(
+        ty.span(),
+        format!(
+            "Can't derive an enum_dispatch variant name for `{}`; use the `Name({})` form instead",
+            ty.into_token_stream(),
+            ty.into_token_stream()
+        ),
+    )
+}
+
+/// Converts a CamelCase identifier to snake_case, for use in the names of generated items. For
+/// example, `LinearKnob` becomes `linear_knob` and `HTTPKnob` becomes `http_knob`.
+pub fn snake_case(ident: &syn::Ident) -> String {
+    let name = ident.to_string();
+    let chars: Vec<char> = name.trim_start_matches("r#").chars().collect();
+    let mut snake_case = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let after_lowercase = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_numeric());
+            let ends_acronym =
+                i > 0 && chars[i - 1].is_uppercase() && matches!(chars.get(i + 1), Some(next) if next.is_lowercase());
+            if after_lowercase || ends_acronym {
+                snake_case.push('_');
+            }
+            snake_case.extend(c.to_lowercase());
+        } else {
+            snake_case.push(*c);
+        }
+    }
+    snake_case
+}
@@ -1,16 +1,30 @@
 //! Provides a utility for generating `enum_dispatch` impl blocks given `EnumDispatchItem` and
 //! `syn::ItemTrait` definitions.
 use crate::proc_macro;
-use proc_macro2;
 use quote::{
     quote,
     ToTokens
 };
-use syn;
 use syn::spanned::Spanned;
 
+use crate::cache;
 use crate::enum_dispatch_item::EnumDispatchItem;
-use crate::enum_dispatch_variant::EnumDispatchVariant;
+use crate::enum_dispatch_variant::{EnumDispatchVariant, Fallback};
+use crate::generated_items::check_collisions;
+use crate::impl_block::{generate_impl_block, is_impl_block};
+use crate::inherent_methods::generate_inherent_methods;
+use crate::json_schema::generate_json_schema;
+use crate::kind_enum::generate_kind_enum;
+use crate::name_registry::generate_name_registry;
+use crate::partial_dispatch::{check_implemented_traits, generate_partial_impls};
+use crate::payload_wrapper::{access_expr, unwrap_payload, wrapping_constructor, Access};
+use crate::pin_projection::{generate_pin_guards, has_pinned_method, has_pinned_receiver, is_pinned_receiver};
+use crate::remote_trait::take_remote_path;
+use crate::serde_repr::generate_serde_impls;
+use crate::shared_fields::generate_field_accessors;
+use crate::superset::generate_subset_conversions;
+use crate::variant_storage::{generate_storage_items, load_expr, storage_selector, store_stmt};
+use crate::visitor::generate_visitor;
 
 /// Name bound to the single enum field in generated match statements. It doesn't really matter
 /// what this is, as long as it's consistent across the left and right sides of generated match
@@ -19,14 +33,56 @@ const FIELDNAME: &str = "inner";
 
 /// Implements the specified trait for the given enum definition, assuming the trait definition is
 /// already present in local storage.
-pub fn add_enum_impls(enum_def: EnumDispatchItem, traitdef: syn::ItemTrait) -> proc_macro2::TokenStream {
-    let traitname = traitdef.ident;
+pub fn add_enum_impls(enum_def: EnumDispatchItem, mut traitdef: syn::ItemTrait) -> proc_macro2::TokenStream {
+    if is_impl_block(&traitdef) {
+        return generate_impl_block(&enum_def, traitdef);
+    }
+    // Traits declared with `remote_trait!` are referred to by their full path, and their methods
+    // are called through it, since they may not be in scope.
+    let remote_path = take_remote_path(&mut traitdef);
+    let traitname = match &remote_path {
+        Some(path) => path.into_token_stream(),
+        None => traitdef.ident.to_owned().into_token_stream(),
+    };
+    let key = match &remote_path {
+        Some(path) => cache::path_key(path),
+        None => traitdef.ident.to_string(),
+    };
+    if has_pinned_method(&traitdef) && !enum_def.options.pinned {
+        return syn::Error::new(
+            enum_def.ident.span(),
+            format!(
+                "`{}` needs the `pinned` option to dispatch the pinned methods of `{}`, since the trait is declared after the enum",
+                enum_def.ident, traitname
+            ),
+        )
+        .to_compile_error();
+    }
+    if enum_def.options.is_partial(&key) {
+        return generate_partial_impls(&enum_def, &traitdef, &traitname, remote_path.as_ref());
+    }
+    let generics = trait_impl_generics(&enum_def, &traitdef, &traitname);
+    let dyn_conversions = if enum_def.options.dyn_conversions && is_object_safe(&traitdef) {
+        generate_dyn_conversions(&enum_def, &traitdef, &traitname, &generics)
+    } else {
+        proc_macro2::TokenStream::new()
+    };
+    let inherent_methods = if enum_def.options.is_inherent(&key) {
+        let (_, ty_generics, _) = traitdef.generics.split_for_impl();
+        generate_inherent_methods(&enum_def, &traitdef, &key, &syn::parse_quote! { #traitname #ty_generics }, &generics)
+    } else {
+        proc_macro2::TokenStream::new()
+    };
     let traitfns = traitdef.items;
 
-    let (impl_generics, ty_generics, where_clause) = traitdef.generics.split_for_impl();
+    let (_, ty_generics, _) = traitdef.generics.split_for_impl();
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, enum_ty_generics, _) = enum_def.generics.split_for_impl();
+    let trait_path: syn::Path = syn::parse_quote! { #traitname #ty_generics };
+    let remote_trait = remote_path.map(|_| &trait_path);
     let enumname = &enum_def.ident.to_owned();
     let trait_impl = quote! {
-        impl #impl_generics #traitname #ty_generics for #enumname #ty_generics #where_clause {
+        impl #impl_generics #trait_path for #enumname #enum_ty_generics #where_clause {
 
         }
     };
@@ -34,51 +90,952 @@ pub fn add_enum_impls(enum_def: EnumDispatchItem, traitdef: syn::ItemTrait) -> p
 
     trait_impl.unsafety = traitdef.unsafety;
 
-    let variants: Vec<&EnumDispatchVariant> = enum_def.variants.iter().collect();
-
     for trait_fn in traitfns {
+        if let syn::TraitItem::Type(assoc) = &trait_fn {
+            match create_assoc_type(assoc, &enum_def, &trait_path) {
+                Ok(assoc_type) => trait_impl.items.push(assoc_type),
+                Err(e) => return e.to_compile_error(),
+            }
+            continue;
+        }
         trait_impl
             .items
-            .push(create_trait_match(trait_fn, &enum_def.ident, &variants));
+            .push(create_trait_match(trait_fn, &enum_def, remote_trait));
+    }
+
+    let mut impls = trait_impl.into_token_stream();
+    impls.extend(dyn_conversions);
+    impls.extend(inherent_methods);
+    impls
+}
+
+/// Combines the generic parameters of the enum and the trait for an impl of one for the other.
+/// Parameters with the same name are assumed to be the same, so an enum can mirror the generics of
+/// a trait. Payloads referring to any of the parameters are required to implement the trait, since
+/// that can't be known in general, like for `AsRef<T>`. The same goes for every payload if the
+/// trait itself is generic.
+fn trait_impl_generics(
+    enum_def: &EnumDispatchItem,
+    traitdef: &syn::ItemTrait,
+    traitname: &proc_macro2::TokenStream,
+) -> syn::Generics {
+    let param_ident = |param: &syn::GenericParam| match param {
+        syn::GenericParam::Type(param) => param.ident.to_owned(),
+        syn::GenericParam::Lifetime(param) => param.lifetime.ident.to_owned(),
+        syn::GenericParam::Const(param) => param.ident.to_owned(),
+    };
+    let mut generics = enum_def.generics.clone();
+    for param in traitdef.generics.params.iter() {
+        let ident = param_ident(param);
+        if generics.params.iter().any(|existing| param_ident(existing) == ident) {
+            continue;
+        }
+        match param {
+            syn::GenericParam::Lifetime(_) => generics.params.insert(generics.lifetimes().count(), param.clone()),
+            _ => generics.params.push(param.clone()),
+        }
+    }
+    let idents: Vec<syn::Ident> = generics.params.iter().map(param_ident).collect();
+    let idents: Vec<&syn::Ident> = idents.iter().collect();
+    let (_, ty_generics, _) = traitdef.generics.split_for_impl();
+    let where_clause = generics.make_where_clause();
+    if let Some(trait_where_clause) = &traitdef.generics.where_clause {
+        where_clause.predicates.extend(trait_where_clause.predicates.iter().cloned());
+    }
+    for variant in enum_def.variants.iter() {
+        let ty = match &variant.ty {
+            Some(ty) if variant.dispatches() && nested_enum(enum_def, variant).is_none() => unwrap_payload(ty, &variant.options).1,
+            _ => continue,
+        };
+        if !traitdef.generics.params.is_empty() || mentions_idents(ty.into_token_stream(), &idents) {
+            where_clause.predicates.push(syn::parse_quote! { #ty: #traitname #ty_generics });
+        }
+    }
+    generics
+}
+
+/// Creates the definition of an associated type for an impl of the trait for the enum. It's taken
+/// from the first dispatching variant's payload, and the payloads of other variants must agree.
+fn create_assoc_type(
+    assoc: &syn::TraitItemType,
+    enum_def: &EnumDispatchItem,
+    trait_path: &syn::Path,
+) -> syn::Result<syn::ImplItem> {
+    let ident = &assoc.ident;
+    match first_payload_type(enum_def) {
+        Some(ty) => Ok(syn::parse_quote! { type #ident = <#ty as #trait_path>::#ident; }),
+        None => Err(syn::Error::new(
+            enum_def.ident.span(),
+            format!(
+                "enum_dispatch can't choose the associated type `{}` for `{}`, since none of its variants dispatch to a payload",
+                ident, enum_def.ident
+            ),
+        )),
     }
+}
 
-    let from_impls = generate_from_impls(&enum_def.ident, &variants, &trait_impl.generics);
+/// Returns the payload type that trait methods of the first dispatching variant are called on,
+/// looking through nested enums.
+fn first_payload_type(enum_def: &EnumDispatchItem) -> Option<syn::Type> {
+    let variant = enum_def.variants.iter().find(|variant| variant.dispatches() && variant.ty.is_some())?;
+    match nested_enum(enum_def, variant) {
+        Some(nested) => first_payload_type(&nested),
+        None => variant.ty.as_ref().map(|ty| unwrap_payload(ty, &variant.options).1.to_owned()),
+    }
+}
 
-    let mut impls = proc_macro2::TokenStream::new();
-    for from_impl in from_impls.iter() {
+/// Checks whether trait objects can be created for the trait, as far as can be told from its
+/// definition. Traits with associated types or constants are treated as not object safe, since
+/// trait objects for them would have to specify their values.
+fn is_object_safe(traitdef: &syn::ItemTrait) -> bool {
+    let self_ident = syn::Ident::new("Self", proc_macro2::Span::call_site());
+    let is_sized = |bound: &syn::TypeParamBound| match bound {
+        syn::TypeParamBound::Trait(bound) => matches!(bound.path.segments.iter().last(), Some(segment) if segment.ident == "Sized"),
+        _ => false,
+    };
+    if traitdef.supertraits.iter().any(is_sized) || has_assoc_types(traitdef) {
+        return false;
+    }
+    traitdef.items.iter().all(|item| {
+        let method = match item {
+            syn::TraitItem::Method(method) => method,
+            _ => return false,
+        };
+        let requires_sized = method.sig.decl.generics.where_clause.iter().flat_map(|where_clause| where_clause.predicates.iter()).any(|predicate| match predicate {
+            syn::WherePredicate::Type(predicate) => {
+                predicate.bounded_ty == syn::parse_quote! { Self } && predicate.bounds.iter().any(is_sized)
+            }
+            _ => false,
+        });
+        if requires_sized {
+            return true;
+        }
+        let has_receiver = matches!(method.sig.decl.inputs.iter().next(), Some(syn::FnArg::SelfRef(_)) | Some(syn::FnArg::SelfValue(_)));
+        let is_generic = method.sig.decl.generics.type_params().next().is_some();
+        let other_inputs = method.sig.decl.inputs.iter().skip(1).map(|input| input.into_token_stream());
+        let mentions_self = other_inputs
+            .chain(Some(method.sig.decl.output.clone().into_token_stream()))
+            .any(|tokens| mentions_idents(tokens, &[&self_ident]));
+        has_receiver && !is_generic && !mentions_self
+    })
+}
+
+/// Checks whether a trait or any of its known supertraits declares associated types, which a
+/// trait object would have to specify.
+fn has_assoc_types(traitdef: &syn::ItemTrait) -> bool {
+    traitdef.items.iter().any(|item| matches!(item, syn::TraitItem::Type(_)))
+        || traitdef.supertraits.iter().any(|bound| match bound {
+            syn::TypeParamBound::Trait(bound) => {
+                matches!(cache::cached_trait(&cache::path_key(&bound.path)), Some(supertrait) if has_assoc_types(&supertrait))
+            }
+            _ => false,
+        })
+}
+
+/// Generates conversions from references to the enum into references to trait objects, and from
+/// the enum into a boxed trait object. These are used by the generic `as_dyn` and `as_dyn_mut`
+/// methods. Only used with the `dyn_conversions` option.
+///
+/// Where possible, the trait object refers directly to the payload, avoiding a second dispatch.
+/// Otherwise, such as for variants with a fallback or a wrapped payload, the enum itself is used.
+fn generate_dyn_conversions(
+    enum_def: &EnumDispatchItem,
+    traitdef: &syn::ItemTrait,
+    traitname: &proc_macro2::TokenStream,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let enumname = &enum_def.ident;
+    let (_, ty_generics, _) = traitdef.generics.split_for_impl();
+    let (_, enum_ty_generics, _) = enum_def.generics.split_for_impl();
+    let mut generics = generics.clone();
+    generics.params.insert(0, syn::parse_quote! { '__enum_dispatch });
+    generics.make_where_clause().predicates.push(syn::parse_quote! { #enumname #enum_ty_generics: '__enum_dispatch });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let binding = syn::Ident::new(FIELDNAME, proc_macro2::Span::call_site());
+    let patterns: Vec<syn::Pat> = enum_def
+        .variants
+        .iter()
+        .filter(|variant| {
+            variant.dispatches()
+                && matches!(&variant.ty, Some(ty) if unwrap_payload(ty, &variant.options).0.is_empty())
+                && storage_selector(enum_def, variant).is_none()
+                && nested_enum(enum_def, variant).is_none()
+        })
+        .map(|variant| variant.pattern(enumname, &binding))
+        .collect();
+    let ref_arms: Vec<proc_macro2::TokenStream> = patterns.iter().map(|pattern| quote! { #pattern => #binding, }).collect();
+    let ref_arms = &ref_arms;
+    let box_arms = patterns.iter().map(|pattern| quote! { #pattern => ::std::boxed::Box::new(#binding), });
+    let dyn_trait = quote! { (dyn #traitname #ty_generics + '__enum_dispatch) };
+    let enum_type = quote! { #enumname #enum_ty_generics };
+    let mut conversions = quote! {
+        impl #impl_generics ::std::convert::From<&'__enum_dispatch #enum_type> for &'__enum_dispatch #dyn_trait #where_clause {
+            #[allow(unreachable_patterns)]
+            fn from(value: &'__enum_dispatch #enum_type) -> Self {
+                match value {
+                    #(#ref_arms)*
+                    other => other,
+                }
+            }
+        }
+
+        impl #impl_generics ::std::convert::From<&'__enum_dispatch mut #enum_type> for &'__enum_dispatch mut #dyn_trait #where_clause {
+            #[allow(unreachable_patterns)]
+            fn from(value: &'__enum_dispatch mut #enum_type) -> Self {
+                match value {
+                    #(#ref_arms)*
+                    other => other,
+                }
+            }
+        }
+    };
+    // The standard library already converts any error into a boxed `dyn Error`.
+    let is_std_error = matches!(syn::parse2::<syn::Path>(traitname.clone()), Ok(path) if cache::path_key(&path) == "std::error::Error");
+    if !is_std_error {
+        conversions.extend(quote! {
+            impl #impl_generics ::std::convert::From<#enum_type> for ::std::boxed::Box<#dyn_trait> #where_clause {
+                #[allow(unreachable_patterns)]
+                fn from(value: #enum_type) -> Self {
+                    match value {
+                        #(#box_arms)*
+                        other => ::std::boxed::Box::new(other),
+                    }
+                }
+            }
+        });
+    }
+    conversions
+}
+
+/// Generates the impls belonging to the enum itself, independent of any linked traits. These are
+/// created once, when the enum is registered. The `trait_bounds` of the traits linked in the enum's
+/// own attributes are only used to bound the payloads passed to visitors.
+pub fn add_enum_conversions(enum_def: &EnumDispatchItem, trait_bounds: &[proc_macro2::TokenStream]) -> proc_macro2::TokenStream {
+    let mut impls = generate_storage_items(enum_def);
+    if let Err(e) = check_duplicate_payloads(enum_def)
+        .and_then(|()| check_flattened_variants(enum_def))
+        .and_then(|()| check_collisions(enum_def, vec![]))
+    {
+        impls.extend(e.to_compile_error());
+        return impls;
+    }
+    let from_impls = generate_from_impls(enum_def);
+    let try_from_impls = if enum_def.options.try_from { generate_try_from_impls(enum_def) } else { vec![] };
+
+    for from_impl in from_impls.iter().chain(try_from_impls.iter()) {
         from_impl.to_tokens(&mut impls);
     }
-    trait_impl.to_tokens(&mut impls);
+    impls.extend(check_implemented_traits(enum_def));
+    if enum_def.options.pinned {
+        impls.extend(generate_pin_guards(enum_def));
+    }
+    if enum_def.options.accessors {
+        impls.extend(generate_accessors(enum_def));
+    }
+    if enum_def.options.dyn_conversions {
+        impls.extend(generate_dyn_methods(enum_def));
+    }
+    impls.extend(generate_field_accessors(enum_def));
+    impls.extend(generate_variant_trait(enum_def));
+    if enum_def.options.introspection {
+        impls.extend(generate_introspection(enum_def));
+    }
+    if enum_def.options.any_conversions {
+        impls.extend(generate_any_conversions(enum_def));
+    }
+    impls.extend(generate_kind_enum(enum_def));
+    impls.extend(generate_serde_impls(enum_def));
+    impls.extend(generate_json_schema(enum_def));
+    impls.extend(generate_name_registry(enum_def));
+    impls.extend(generate_subset_conversions(enum_def));
+    impls.extend(generate_visitor(enum_def, trait_bounds));
     impls
 }
 
-/// Generates impls of std::convert::From for each enum variant.
-fn generate_from_impls(enumname: &syn::Ident, enumvariants: &[&EnumDispatchVariant], generics: &syn::Generics) -> Vec<syn::ItemImpl> {
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    enumvariants
-        .iter()
-        .map(|variant| {
-            let variant_name = &variant.ident;
-            let variant_type = &variant.ty;
+/// Generates impls of std::convert::From for each enum variant. Variants with additional fields
+/// only get an impl if they can be filled in with `Default::default()`, and variants or enums
+/// marked with `no_from` don't get any.
+///
+/// Conversions always take the unboxed payload, even if the variant is boxed by `box_above`.
+fn generate_from_impls(enum_def: &EnumDispatchItem) -> Vec<syn::ItemImpl> {
+    let enumname = &enum_def.ident;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let value = syn::Ident::new("v", proc_macro2::Span::call_site());
+    from_conversions(enum_def, &value)
+        .into_iter()
+        .map(|Conversion { source, body, .. }| {
             let impl_block = quote! {
-                impl #impl_generics ::std::convert::From<#variant_type> for #enumname #ty_generics #where_clause {
-                    fn from(v: #variant_type) -> #enumname #ty_generics {
-                        #enumname::#variant_name(v)
+                impl #impl_generics ::std::convert::From<#source> for #enumname #ty_generics #where_clause {
+                    fn from(#value: #source) -> #enumname #ty_generics {
+                        #body
                     }
                 }
             };
             syn::parse(impl_block.into()).unwrap()
-        }).collect()
+        })
+        .collect()
+}
+
+/// Generates inherent methods for checking the variant of the enum and extracting its payload,
+/// named after the snake_case name of each variant: `is_*` for every variant, as well as `as_*`,
+/// `as_*_mut`, and `into_*` for variants with a payload. Only used with the `accessors` option.
+fn generate_accessors(enum_def: &EnumDispatchItem) -> proc_macro2::TokenStream {
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let binding = syn::Ident::new(FIELDNAME, proc_macro2::Span::call_site());
+    let mut methods = proc_macro2::TokenStream::new();
+    for variant in enum_def.variants.iter() {
+        let variant_name = &variant.ident;
+        let snake_case_name = variant.snake_case_name();
+        let method_name = |format: &str| syn::Ident::new(&format.replace("{}", &snake_case_name), variant_name.span());
+        let is_variant = method_name("is_{}");
+        let wildcard_pattern = variant.wildcard_pattern(enumname);
+        let doc = format!("Returns `true` if this is the `{}` variant.", variant_name);
+        methods.extend(quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #is_variant(&self) -> bool {
+                match self {
+                    #wildcard_pattern => true,
+                    _ => false,
+                }
+            }
+        });
+
+        let ty = match &variant.ty {
+            Some(ty) => ty,
+            None => continue,
+        };
+        let (as_variant, as_variant_mut, into_variant) =
+            (method_name("as_{}"), method_name("as_{}_mut"), method_name("into_{}"));
+        let pattern = variant.pattern(enumname, &binding);
+        let shared = load_expr(enum_def, variant, &binding, Access::Shared);
+        let mutable = load_expr(enum_def, variant, &binding, Access::Mutable);
+        let owned = load_expr(enum_def, variant, &binding, Access::Owned);
+        let as_doc = format!("Returns a reference to the payload of the `{}` variant, if this is that variant.", variant_name);
+        let as_mut_doc = format!("Returns a mutable reference to the payload of the `{}` variant, if this is that variant.", variant_name);
+        let into_doc = format!("Converts into the payload of the `{}` variant, or returns `self` if this is another variant.", variant_name);
+        methods.extend(quote! {
+            #[doc = #as_doc]
+            #[inline]
+            #vis fn #as_variant(&self) -> ::std::option::Option<&#ty> {
+                match self {
+                    #pattern => ::std::option::Option::Some(#shared),
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            #[doc = #as_mut_doc]
+            #[inline]
+            #vis fn #as_variant_mut(&mut self) -> ::std::option::Option<&mut #ty> {
+                match self {
+                    #pattern => ::std::option::Option::Some(#mutable),
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            #[doc = #into_doc]
+            #[inline]
+            #vis fn #into_variant(self) -> ::std::result::Result<#ty, Self> {
+                match self {
+                    #pattern => ::std::result::Result::Ok(#owned),
+                    other => ::std::result::Result::Err(other),
+                }
+            }
+        });
+    }
+    quote! {
+        #[allow(dead_code, unreachable_patterns)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            #methods
+        }
+    }
+}
+
+/// Generates the generic `as_dyn`, `as_dyn_mut`, and `into_box_dyn` methods, which use the
+/// conversions generated by `generate_dyn_conversions` for each object-safe linked trait. Only
+/// used with the `dyn_conversions` option.
+fn generate_dyn_methods(enum_def: &EnumDispatchItem) -> proc_macro2::TokenStream {
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let mut methods = proc_macro2::TokenStream::new();
+    methods.extend(quote! {
+        /// Borrows the value as a trait object of any object-safe trait linked to the enum, like
+        /// `&dyn Trait`. Where possible, the trait object refers directly to the payload.
+        #[inline]
+        #vis fn as_dyn<'__enum_dispatch, __Dyn: ?::std::marker::Sized>(&'__enum_dispatch self) -> &'__enum_dispatch __Dyn
+        where
+            &'__enum_dispatch __Dyn: ::std::convert::From<&'__enum_dispatch Self>,
+        {
+            ::std::convert::From::from(self)
+        }
+    });
+    methods.extend(quote! {
+        /// Mutably borrows the value as a trait object of any object-safe trait linked to the
+        /// enum, like `&mut dyn Trait`. Where possible, the trait object refers directly to the
+        /// payload.
+        #[inline]
+        #vis fn as_dyn_mut<'__enum_dispatch, __Dyn: ?::std::marker::Sized>(&'__enum_dispatch mut self) -> &'__enum_dispatch mut __Dyn
+        where
+            &'__enum_dispatch mut __Dyn: ::std::convert::From<&'__enum_dispatch mut Self>,
+        {
+            ::std::convert::From::from(self)
+        }
+    });
+    methods.extend(quote! {
+        /// Moves the value into a boxed trait object of any object-safe trait linked to the enum,
+        /// like `Box<dyn Trait>`. Where possible, only the payload is boxed.
+        #[inline]
+        #vis fn into_box_dyn<__Dyn: ?::std::marker::Sized>(self) -> ::std::boxed::Box<__Dyn>
+        where
+            ::std::boxed::Box<__Dyn>: ::std::convert::From<Self>,
+        {
+            ::std::convert::From::from(self)
+        }
+    });
+    quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            #methods
+        }
+    }
+}
+
+/// Lists the variants whose payload type identifies them, along with that type. Payload types
+/// shared by several variants, or referring to the enum's generic type parameters, are skipped,
+/// since trait impls for them could overlap.
+fn distinct_payload_variants(enum_def: &EnumDispatchItem) -> Vec<(&EnumDispatchVariant, &syn::Type)> {
+    let payload_types: Vec<String> = enum_def
+        .variants
+        .iter()
+        .filter_map(|variant| variant.ty.as_ref())
+        .map(|ty| ty.into_token_stream().to_string())
+        .collect();
+    enum_def
+        .variants
+        .iter()
+        .filter_map(|variant| Some((variant, variant.ty.as_ref()?)))
+        .filter(|(_, ty)| {
+            let ty_string = ty.into_token_stream().to_string();
+            payload_types.iter().filter(|other| **other == ty_string).count() == 1
+                && !mentions_type_params(ty, &enum_def.generics)
+        })
+        .collect()
+}
+
+/// Generates impls of std::convert::TryFrom from the enum (or a reference to it) into each
+/// distinct payload type (or a reference to it). Only used with the `try_from` option.
+///
+/// With the `dyn_conversions` option, boxed trait object payloads are skipped, since the enum
+/// already converts into them infallibly.
+fn generate_try_from_impls(enum_def: &EnumDispatchItem) -> Vec<syn::ItemImpl> {
+    let enumname = &enum_def.ident;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let mut ref_generics = enum_def.generics.clone();
+    ref_generics.params.insert(0, syn::parse_quote! { '__enum_dispatch });
+    let (ref_impl_generics, _, _) = ref_generics.split_for_impl();
+    let binding = syn::Ident::new(FIELDNAME, proc_macro2::Span::call_site());
+    let mut impls = vec![];
+    for (variant, ty) in distinct_payload_variants(enum_def) {
+        if enum_def.options.dyn_conversions && is_box_dyn(ty) {
+            continue;
+        }
+        let pattern = variant.pattern(enumname, &binding);
+        let shared = load_expr(enum_def, variant, &binding, Access::Shared);
+        let mutable = load_expr(enum_def, variant, &binding, Access::Mutable);
+        let owned = load_expr(enum_def, variant, &binding, Access::Owned);
+        let blocks = vec![
+            quote! {
+                impl #impl_generics ::std::convert::TryFrom<#enumname #ty_generics> for #ty #where_clause {
+                    type Error = #enumname #ty_generics;
+                    fn try_from(value: #enumname #ty_generics) -> ::std::result::Result<Self, Self::Error> {
+                        match value {
+                            #pattern => ::std::result::Result::Ok(#owned),
+                            other => ::std::result::Result::Err(other),
+                        }
+                    }
+                }
+            },
+            quote! {
+                impl #ref_impl_generics ::std::convert::TryFrom<&'__enum_dispatch #enumname #ty_generics> for &'__enum_dispatch #ty #where_clause {
+                    type Error = &'__enum_dispatch #enumname #ty_generics;
+                    fn try_from(value: &'__enum_dispatch #enumname #ty_generics) -> ::std::result::Result<Self, Self::Error> {
+                        match value {
+                            #pattern => ::std::result::Result::Ok(#shared),
+                            other => ::std::result::Result::Err(other),
+                        }
+                    }
+                }
+            },
+            quote! {
+                impl #ref_impl_generics ::std::convert::TryFrom<&'__enum_dispatch mut #enumname #ty_generics> for &'__enum_dispatch mut #ty #where_clause {
+                    type Error = &'__enum_dispatch mut #enumname #ty_generics;
+                    fn try_from(value: &'__enum_dispatch mut #enumname #ty_generics) -> ::std::result::Result<Self, Self::Error> {
+                        match value {
+                            #pattern => ::std::result::Result::Ok(#mutable),
+                            other => ::std::result::Result::Err(other),
+                        }
+                    }
+                }
+            },
+        ];
+        impls.extend(blocks.into_iter().map(|block| syn::parse2(block).unwrap()));
+    }
+    impls
+}
+
+/// Checks whether the type is `Box<dyn Trait>`.
+fn is_box_dyn(ty: &syn::Type) -> bool {
+    let segment = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.last().map(|pair| pair.into_value()),
+        _ => None,
+    };
+    match segment {
+        Some(segment) if segment.ident == "Box" => match &segment.arguments {
+            syn::PathArguments::AngleBracketed(args) => {
+                matches!(args.args.first().map(|pair| pair.into_value()), Some(syn::GenericArgument::Type(syn::Type::TraitObject(_))))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Generates the trait named by the `variant_trait` option, implemented by each distinct payload
+/// type. It allows generic methods on the enum, like `get::<T>()`, to access the payload by its
+/// type.
+///
+/// Procedural macro crates can't export traits, so a trait is generated next to each enum rather
+/// than being shared between them.
+fn generate_variant_trait(enum_def: &EnumDispatchItem) -> proc_macro2::TokenStream {
+    let trait_name = match &enum_def.options.variant_trait {
+        Some(trait_name) => trait_name,
+        None => return proc_macro2::TokenStream::new(),
+    };
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let generics = &enum_def.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let trait_doc = format!(
+        "Implemented by the payload types of `{}`, allowing them to be accessed by type using methods like `{}::get`.",
+        enumname, enumname
+    );
+    let binding = syn::Ident::new(FIELDNAME, proc_macro2::Span::call_site());
+    let impls = distinct_payload_variants(enum_def).into_iter().map(|(variant, ty)| {
+        let pattern = variant.pattern(enumname, &binding);
+        let shared = load_expr(enum_def, variant, &binding, Access::Shared);
+        let mutable = load_expr(enum_def, variant, &binding, Access::Mutable);
+        let owned = load_expr(enum_def, variant, &binding, Access::Owned);
+        quote! {
+            #[allow(unreachable_patterns)]
+            impl #impl_generics #trait_name #ty_generics for #ty #where_clause {
+                #[inline]
+                fn from_enum_ref<'__enum_dispatch>(value: &'__enum_dispatch #enumname #ty_generics) -> ::std::option::Option<&'__enum_dispatch Self> {
+                    match value {
+                        #pattern => ::std::option::Option::Some(#shared),
+                        _ => ::std::option::Option::None,
+                    }
+                }
+                #[inline]
+                fn from_enum_mut<'__enum_dispatch>(value: &'__enum_dispatch mut #enumname #ty_generics) -> ::std::option::Option<&'__enum_dispatch mut Self> {
+                    match value {
+                        #pattern => ::std::option::Option::Some(#mutable),
+                        _ => ::std::option::Option::None,
+                    }
+                }
+                #[inline]
+                fn from_enum(value: #enumname #ty_generics) -> ::std::result::Result<Self, #enumname #ty_generics> {
+                    match value {
+                        #pattern => ::std::result::Result::Ok(#owned),
+                        other => ::std::result::Result::Err(other),
+                    }
+                }
+            }
+        }
+    });
+    let mut variant_trait = quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_name #generics: ::std::marker::Sized #where_clause {
+            /// Returns a reference to the payload if the value holds this type.
+            fn from_enum_ref<'__enum_dispatch>(value: &'__enum_dispatch #enumname #ty_generics) -> ::std::option::Option<&'__enum_dispatch Self>;
+            /// Returns a mutable reference to the payload if the value holds this type.
+            fn from_enum_mut<'__enum_dispatch>(value: &'__enum_dispatch mut #enumname #ty_generics) -> ::std::option::Option<&'__enum_dispatch mut Self>;
+            /// Converts the value into its payload if it holds this type, or returns it unchanged.
+            fn from_enum(value: #enumname #ty_generics) -> ::std::result::Result<Self, #enumname #ty_generics>;
+        }
+
+        #(#impls)*
+    };
+    let methods = quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            /// Returns a reference to the payload if it is of type `__Variant`.
+            #[inline]
+            #vis fn get<'__enum_dispatch, __Variant: #trait_name #ty_generics>(&'__enum_dispatch self) -> ::std::option::Option<&'__enum_dispatch __Variant> {
+                __Variant::from_enum_ref(self)
+            }
+
+            /// Returns a mutable reference to the payload if it is of type `__Variant`.
+            #[inline]
+            #vis fn get_mut<'__enum_dispatch, __Variant: #trait_name #ty_generics>(&'__enum_dispatch mut self) -> ::std::option::Option<&'__enum_dispatch mut __Variant> {
+                __Variant::from_enum_mut(self)
+            }
+
+            /// Converts into the payload if it is of type `__Variant`, or returns `self` otherwise.
+            #[inline]
+            #vis fn try_into_variant<__Variant: #trait_name #ty_generics>(self) -> ::std::result::Result<__Variant, Self> {
+                __Variant::from_enum(self)
+            }
+
+            /// Returns `true` if the payload is of type `__Variant`.
+            #[inline]
+            #vis fn contains_type<__Variant: #trait_name #ty_generics>(&self) -> bool {
+                __Variant::from_enum_ref(self).is_some()
+            }
+        }
+    };
+    variant_trait.extend(methods);
+    variant_trait
+}
+
+/// Generates inherent methods and constants describing the enum's variants, for logging and
+/// similar purposes. Only used with the `introspection` option.
+fn generate_introspection(enum_def: &EnumDispatchItem) -> proc_macro2::TokenStream {
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let variant_count = enum_def.variants.len();
+    let variant_names: Vec<String> = enum_def.variants.iter().map(|variant| variant.ident.to_string()).collect();
+    let patterns: Vec<syn::Pat> = enum_def.variants.iter().map(|variant| variant.wildcard_pattern(enumname)).collect();
+    let patterns = &patterns;
+    let indices = 0..variant_count;
+    let type_names = enum_def.variants.iter().map(|variant| match &variant.ty {
+        Some(ty) => quote! { ::std::any::type_name::<#ty>() },
+        None => quote! { ::std::any::type_name::<Self>() },
+    });
+    let names = &variant_names;
+    let names_doc = format!("The names of the variants of `{}`, in declaration order.", enumname);
+    quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            /// The number of variants.
+            #vis const VARIANT_COUNT: usize = #variant_count;
+            #[doc = #names_doc]
+            #vis const VARIANT_NAMES: &'static [&'static str] = &[#(#names),*];
+
+            /// Returns the name of the current variant.
+            #[inline]
+            #vis fn variant_name(&self) -> &'static str {
+                match self {
+                    #(#patterns => #names,)*
+                }
+            }
+
+            /// Returns the position of the current variant in the enum's declaration, which is also
+            /// its position in `VARIANT_NAMES`.
+            #[inline]
+            #vis fn variant_index(&self) -> usize {
+                match self {
+                    #(#patterns => #indices,)*
+                }
+            }
+
+            /// Returns the name of the current variant's payload type, as given by
+            /// `std::any::type_name`. Variants without a payload return the name of the enum type.
+            #[inline]
+            #vis fn variant_type_name(&self) -> &'static str {
+                match self {
+                    #(#patterns => #type_names,)*
+                }
+            }
+        }
+    }
+}
+
+/// Generates `try_from_any` and `try_from_any_ref`, which convert type-erased values back into the
+/// enum by attempting to downcast them to each distinct payload type in declaration order. Only
+/// used with the `any_conversions` option.
+///
+/// `Any` requires `'static` types, so payloads that refer to the enum's lifetime parameters are
+/// skipped. Since `try_from_any_ref` has to clone the payload, it requires every candidate payload
+/// type to implement `Clone`. The bounds are higher-ranked so that they are only checked where the
+/// method is used, rather than rejecting the enum outright.
+fn generate_any_conversions(enum_def: &EnumDispatchItem) -> proc_macro2::TokenStream {
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let lifetimes: Vec<&syn::Ident> = enum_def.generics.lifetimes().map(|param| &param.lifetime.ident).collect();
+    let value = syn::Ident::new("value", proc_macro2::Span::call_site());
+    let candidates: Vec<(&syn::Type, syn::Expr, proc_macro2::TokenStream)> = distinct_payload_variants(enum_def)
+        .into_iter()
+        .filter(|(_, ty)| !mentions_idents(ty.into_token_stream(), &lifetimes))
+        .filter_map(|(variant, ty)| Some((ty, variant.constructor(enumname, &value)?, store_stmt(enum_def, variant, &value))))
+        .collect();
+    let owned_attempts = candidates.iter().map(|(ty, constructor, store)| {
+        quote! {
+            let #value = match #value.downcast::<#ty>() {
+                ::std::result::Result::Ok(#value) => {
+                    let #value = *#value;
+                    #store
+                    return ::std::result::Result::Ok(#constructor);
+                }
+                ::std::result::Result::Err(#value) => #value,
+            };
+        }
+    });
+    // As for `default_value`, the macro can't tell which payload types implement `Clone`, so a
+    // probe method implemented for all `T: Clone` is preferred over one implemented for references
+    // to any `T`, which skips payload types that can't be cloned.
+    let ref_attempts = candidates.iter().map(|(ty, constructor, store)| {
+        quote! {
+            if let ::std::option::Option::Some(#value) = #value.downcast_ref::<#ty>() {
+                if let ::std::option::Option::Some(#value) = (&Probe::<#ty>(::std::marker::PhantomData)).clone_of(#value) {
+                    #store
+                    return ::std::option::Option::Some(#constructor);
+                }
+            }
+        }
+    });
+    let probe = clone_probe();
+    quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            /// Converts a boxed `Any` into the enum, if it holds one of the payload types. Otherwise,
+            /// the box is returned unchanged.
+            #vis fn try_from_any(#value: ::std::boxed::Box<dyn ::std::any::Any>) -> ::std::result::Result<Self, ::std::boxed::Box<dyn ::std::any::Any>> {
+                #(#owned_attempts)*
+                ::std::result::Result::Err(#value)
+            }
+
+            /// Creates a value of the enum from a clone of the value behind an `&dyn Any`, if it is
+            /// one of the payload types that implement `Clone`.
+            #vis fn try_from_any_ref(#value: &dyn ::std::any::Any) -> ::std::option::Option<Self> {
+                #probe
+                #(#ref_attempts)*
+                ::std::option::Option::None
+            }
+        }
+    }
+}
+
+/// Defines the `Probe` type used by `generate_any_conversions` to clone payloads whose types
+/// implement `Clone`.
+fn clone_probe() -> proc_macro2::TokenStream {
+    quote! {
+        struct Probe<T>(::std::marker::PhantomData<T>);
+        trait ViaClone<T> {
+            fn clone_of(&self, value: &T) -> ::std::option::Option<T>;
+        }
+        impl<T: ::std::clone::Clone> ViaClone<T> for Probe<T> {
+            fn clone_of(&self, value: &T) -> ::std::option::Option<T> {
+                ::std::option::Option::Some(::std::clone::Clone::clone(value))
+            }
+        }
+        trait WithoutClone<T> {
+            fn clone_of(&self, value: &T) -> ::std::option::Option<T>;
+        }
+        impl<T> WithoutClone<T> for &Probe<T> {
+            fn clone_of(&self, _: &T) -> ::std::option::Option<T> {
+                ::std::option::Option::None
+            }
+        }
+    }
+}
+
+/// Returns an error if two variants would both get a `From` impl for the same payload type.
+fn check_duplicate_payloads(enum_def: &EnumDispatchItem) -> syn::parse::Result<()> {
+    if enum_def.options.no_from {
+        return Ok(());
+    }
+    let value = syn::Ident::new("v", proc_macro2::Span::call_site());
+    let converted: Vec<(&EnumDispatchVariant, &syn::Type, String)> = enum_def
+        .variants
+        .iter()
+        .filter(|variant| !variant.options.no_from && variant.constructor(&enum_def.ident, &value).is_some())
+        .filter_map(|variant| {
+            let ty = variant.ty.as_ref()?;
+            Some((variant, ty, ty.into_token_stream().to_string()))
+        })
+        .collect();
+    for (i, (variant, ty, ty_string)) in converted.iter().enumerate() {
+        if let Some((previous, _, _)) = converted.iter().take(i).find(|(_, _, previous)| previous == ty_string) {
+            return Err(syn::Error::new(
+                ty.span(),
+                format!(
+                    "enum_dispatch variants `{}` and `{}` both hold `{}`, so `From<{}>` can't be implemented for both; add `#[enum_dispatch(no_from)]` to one of them",
+                    previous.ident,
+                    variant.ident,
+                    ty_string,
+                    ty_string
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A type that an enum can be converted from, along with the body of the conversion from a value
+/// of that type.
+struct Conversion {
+    source: syn::Type,
+    body: proc_macro2::TokenStream,
+    /// `true` if the source is the payload type of a variant, rather than something it is
+    /// converted from.
+    direct: bool,
+}
+
+/// Lists the conversions that should be implemented for the enum, taking the value to convert as
+/// `value`.
+///
+/// Besides each variant's payload type, this includes the type wrapped by payloads in a smart
+/// pointer or lock, as well as the types that a nested `enum_dispatch` enum payload can be
+/// converted from. These indirect conversions are only included if they don't overlap with any
+/// other conversion.
+fn from_conversions(enum_def: &EnumDispatchItem, value: &syn::Ident) -> Vec<Conversion> {
+    let enumname = &enum_def.ident;
+    let mut payload_types = vec![];
+    let mut candidates = vec![];
+    if enum_def.options.no_from {
+        return candidates;
+    }
+    for variant in enum_def.variants.iter() {
+        let variant_type = match variant.ty.as_ref() {
+            Some(variant_type) => variant_type,
+            None => continue,
+        };
+        payload_types.push(variant_type.into_token_stream().to_string());
+        if variant.options.no_from {
+            continue;
+        }
+        let constructor = match variant.constructor(enumname, value) {
+            Some(constructor) => constructor,
+            None => continue,
+        };
+        let store = store_stmt(enum_def, variant, value);
+        candidates.push(Conversion {
+            source: variant_type.clone(),
+            body: quote! { #store #constructor },
+            direct: true,
+        });
+
+        if let Some((inner, wrap)) = wrapping_constructor(variant_type, value, &variant.options) {
+            candidates.push(Conversion {
+                source: inner.clone(),
+                body: quote! {
+                    let #value = #wrap;
+                    #store
+                    #constructor
+                },
+                direct: false,
+            });
+        }
+
+        if let Some(nested) = nested_enum(enum_def, variant) {
+            for conversion in from_conversions(&nested, value) {
+                candidates.push(Conversion {
+                    body: quote! {
+                        let #value = <#variant_type>::from(#value);
+                        #store
+                        #constructor
+                    },
+                    source: conversion.source,
+                    direct: false,
+                });
+            }
+        }
+    }
+    let sources: Vec<String> = candidates
+        .iter()
+        .filter(|candidate| !candidate.direct)
+        .map(|candidate| (&candidate.source).into_token_stream().to_string())
+        .chain(payload_types)
+        .collect();
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            if candidate.direct {
+                return true;
+            }
+            let source = (&candidate.source).into_token_stream().to_string();
+            sources.iter().filter(|other| **other == source).count() == 1
+                && !mentions_type_params(&candidate.source, &enum_def.generics)
+        })
+        .collect()
+}
+
+/// Returns the definition of the registered `enum_dispatch` enum that a variant marked with
+/// `flatten` holds, if any. Enums are registered by name, so only payloads naming a non-generic
+/// enum that was registered before this one are found.
+pub fn nested_enum(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant) -> Option<EnumDispatchItem> {
+    if !variant.options.flatten {
+        return None;
+    }
+    let path = nested_enum_path(variant)?;
+    if path.is_ident(enum_def.ident.clone()) {
+        return None;
+    }
+    cache::cached_enum(&cache::path_key(path)).filter(|nested| nested.generics.params.is_empty())
+}
+
+/// Returns the path naming the payload type of the variant, if it has no generic arguments.
+fn nested_enum_path(variant: &EnumDispatchVariant) -> Option<&syn::Path> {
+    match variant.ty.as_ref()? {
+        syn::Type::Path(syn::TypePath { qself: None, path })
+            if path.segments.iter().all(|segment| segment.arguments.is_empty()) =>
+        {
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+/// Returns an error if a variant marked with `flatten` doesn't hold a registered enum.
+fn check_flattened_variants(enum_def: &EnumDispatchItem) -> syn::Result<()> {
+    for variant in enum_def.variants.iter().filter(|variant| variant.options.flatten) {
+        if nested_enum(enum_def, variant).is_none() {
+            let span = variant.ty.as_ref().map_or(variant.ident.span(), |ty| ty.span());
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Variant `{}` can't be flattened, since its payload doesn't name a non-generic enum_dispatch enum registered before `{}`; enums are registered by name, so import it and refer to it by name",
+                    variant.ident, enum_def.ident
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether a type refers to any of the given generic type parameters. Conversions from such
+/// types could overlap with one another, so they are not generated.
+fn mentions_type_params(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    let params: Vec<&syn::Ident> = generics.type_params().map(|param| &param.ident).collect();
+    mentions_idents(ty.into_token_stream(), &params)
+}
+
+/// Checks whether any of the given identifiers appear anywhere within the tokens.
+pub fn mentions_idents(tokens: proc_macro2::TokenStream, idents: &[&syn::Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => idents.contains(&&ident),
+        proc_macro2::TokenTree::Group(group) => mentions_idents(group.stream(), idents),
+        _ => false,
+    })
 }
 
 /// Used to keep track of the 'self' arguments in a trait's function signature.
 /// Static -> no 'self' arguments
-/// ByReference -> &self, &mut self
+/// ByReference -> &self
+/// ByMutableReference -> &mut self
 /// ByValue -> self, mut self
+/// ByPinnedMutableReference -> self: Pin<&mut Self>
 enum MethodType {
     Static,
     ByReference,
+    ByMutableReference,
     ByValue,
+    ByPinnedMutableReference,
 }
 
 /// Parses the arguments of a trait method's signature, returning all non-self arguments as well as
@@ -93,14 +1050,22 @@ fn extract_fn_args(
     let new_args: Vec<syn::Ident> = trait_args
         .iter()
         .filter_map(|arg| match arg {
-            syn::FnArg::SelfRef(_) => {
-                method_type = MethodType::ByReference;
+            syn::FnArg::SelfRef(self_ref) => {
+                method_type = if self_ref.mutability.is_some() {
+                    MethodType::ByMutableReference
+                } else {
+                    MethodType::ByReference
+                };
                 None
             }
             syn::FnArg::SelfValue(_) => {
                 method_type = MethodType::ByValue;
                 None
             }
+            arg if is_pinned_receiver(arg) => {
+                method_type = MethodType::ByPinnedMutableReference;
+                None
+            }
             syn::FnArg::Captured(syn::ArgCaptured {
                 pat: syn::Pat::Ident(syn::PatIdent { ident, .. }),
                 ..
@@ -118,14 +1083,58 @@ fn extract_fn_args(
 }
 
 /// Creates a method call that can be used in the match arms of all non-static method
-/// implementations.
-fn create_trait_fn_call(trait_method: &syn::TraitItemMethod) -> syn::ExprCall {
+/// implementations. If the variant's payload is wrapped in a smart pointer or lock, the method is
+/// called on the wrapped value instead.
+///
+/// Methods of remote traits taking `self` by reference are called through the trait's path, since
+/// other traits in scope, like `Debug` and `Display`, may have methods with the same name. Deref
+/// coercion takes the place of method call autoderef. Owned receivers can't be coerced, but the
+/// trait being implemented is always in scope within its impl, so they use a method call.
+///
+/// Methods taking `self: Pin<&mut Self>` are called on the pinned payload, bound by a match on the
+/// projected receiver.
+fn create_trait_fn_call(
+    trait_method: &syn::TraitItemMethod,
+    variant: &EnumDispatchVariant,
+    remote_trait: Option<&syn::Path>,
+) -> syn::Expr {
     let trait_args = trait_method.to_owned().sig.decl.inputs;
-    let (method_type, args) = extract_fn_args(trait_args);
+    let (method_type, mut args) = extract_fn_args(trait_args);
+    if let MethodType::ByPinnedMutableReference = method_type {
+        // The payload is pinned as it's declared, without unwrapping any smart pointers, so that
+        // payloads like `Pin<Box<dyn Future>>` work as expected.
+        let fieldname = syn::Ident::new(FIELDNAME, trait_method.span());
+        let trait_method_name = &trait_method.sig.ident;
+        // SAFETY: the payload was projected out of the pinned enum by `create_match_expr`, and the
+        // guards generated by `pin_projection` keep it from being moved while the enum is pinned.
+        return syn::parse_quote! {
+            unsafe { ::std::pin::Pin::new_unchecked(#fieldname) }.#trait_method_name(#args)
+        };
+    }
+    let is_reference = matches!(method_type, MethodType::ByReference | MethodType::ByMutableReference);
+    if let (Some(remote_trait), Some(ty), true) = (remote_trait, &variant.ty, is_reference) {
+        let fieldname = syn::Ident::new(FIELDNAME, trait_method.span());
+        let trait_method_name = &trait_method.sig.ident;
+        let inner_ty = unwrap_payload(ty, &variant.options).1;
+        let receiver: syn::Expr = if let MethodType::ByMutableReference = method_type {
+            let receiver = access_expr(&fieldname, ty, Access::Mutable, &variant.options);
+            syn::parse_quote! { &mut *#receiver }
+        } else {
+            let receiver = access_expr(&fieldname, ty, Access::Shared, &variant.options);
+            syn::parse_quote! { &*#receiver }
+        };
+        args.insert(0, receiver);
+        return syn::parse_quote! { <#inner_ty as #remote_trait>::#trait_method_name(#args) };
+    }
 
-    syn::ExprCall {
+    syn::Expr::from(syn::ExprCall {
         attrs: vec![],
         func: {
+            let access = match method_type {
+                MethodType::ByMutableReference => Access::Mutable,
+                MethodType::ByValue => Access::Owned,
+                _ => Access::Shared,
+            };
             if let MethodType::Static = method_type {
                 // Trait calls can be created when the inner type is known, like this:
                 //
@@ -139,42 +1148,75 @@ fn create_trait_fn_call(trait_method: &syn::TraitItemMethod) -> syn::ExprCall {
             } else {
                 let fieldname = syn::Ident::new(FIELDNAME, trait_method.span());
                 let trait_method_name = &trait_method.sig.ident;
-                Box::new(syn::parse_quote! { #fieldname.#trait_method_name })
+                let receiver = match &variant.ty {
+                    Some(ty) => access_expr(&fieldname, ty, access, &variant.options),
+                    None => fieldname.into_token_stream(),
+                };
+                Box::new(syn::parse_quote! { #receiver.#trait_method_name })
             }
         },
         paren_token: Default::default(),
         args,
+    })
+}
+
+/// Creates the body of a match arm for a variant that uses a fallback instead of dispatching the
+/// trait method to its payload.
+fn create_fallback_expr(
+    fallback: &Fallback,
+    trait_method: &syn::TraitItemMethod,
+    variant_name: &syn::Ident,
+) -> syn::Expr {
+    match fallback {
+        Fallback::Function(function) => {
+            let (_, args) = extract_fn_args(trait_method.sig.decl.inputs.to_owned());
+            let args = args.iter();
+            syn::parse_quote! { #function(self #(, #args)*) }
+        }
+        Fallback::DefaultMethod => match &trait_method.default {
+            Some(block) => syn::Expr::Block(syn::ExprBlock {
+                attrs: vec![],
+                label: None,
+                block: block.to_owned(),
+            }),
+            None => {
+                let error = syn::Error::new(
+                    variant_name.span(),
+                    format!(
+                        "enum_dispatch variant `{}` uses the default method fallback, but `{}` has no default implementation",
+                        variant_name, trait_method.sig.ident
+                    ),
+                )
+                .to_compile_error();
+                syn::parse_quote! { #error }
+            }
+        },
+        Fallback::DefaultValue => syn::parse_quote! { ::std::default::Default::default() },
     }
 }
 
 /// Constructs a match expression that matches on all variants of the specified enum, creating a
-/// binding to their single field and calling the provided trait method on each.
+/// binding to their payload field and calling the provided trait method on each. Variants with a
+/// fallback use it instead.
 fn create_match_expr(
     trait_method: &syn::TraitItemMethod,
-    enum_name: &syn::Ident,
-    enumvariants: &[&EnumDispatchVariant],
+    enum_def: &EnumDispatchItem,
+    remote_trait: Option<&syn::Path>,
 ) -> syn::Expr {
-    let trait_fn_call = create_trait_fn_call(trait_method);
+    let match_arms = create_match_arms(trait_method, enum_def, remote_trait, &|pat| pat);
 
-    // Creates a Vec containing a match arm for every enum variant
-    let match_arms = enumvariants
-        .iter()
-        .map(|variant| {
-            let variant_name = &variant.ident;
-            syn::Arm {
-            attrs: vec![],
-            leading_vert: None,
-            pats: {
-                let mut segments = syn::punctuated::Punctuated::new();
-                let fieldname = syn::Ident::new(FIELDNAME, variant.span());
-                segments.push(syn::parse_quote! {#enum_name::#variant_name(#fieldname)});
-                segments
-            },
-            guard: None,
-            fat_arrow_token: Default::default(),
-            body: Box::new(syn::Expr::from(trait_fn_call.to_owned())),
-            comma: Some(Default::default()),
-        }}).collect();
+    // Pinned receivers are projected onto the payload. The receiver is reborrowed so that
+    // fallbacks can still use it.
+    //
+    // SAFETY: nothing is moved out of the enum here. Payloads are only pinned again by
+    // `create_trait_fn_call`, and fallbacks only get the reborrowed, still pinned receiver.
+    if has_pinned_receiver(trait_method) {
+        return syn::parse_quote! {
+            match unsafe { ::std::pin::Pin::get_unchecked_mut(::std::pin::Pin::as_mut(&mut self)) } {
+                #(#match_arms)*
+            }
+        };
+    }
 
     // Creates the match expression
     syn::Expr::from(syn::ExprMatch {
@@ -200,24 +1242,94 @@ fn create_match_expr(
     })
 }
 
+/// Creates a match arm for every variant of the enum, with patterns wrapped by `outer`.
+///
+/// Variants whose payload is another registered `enum_dispatch` enum are flattened into an arm for
+/// each of the nested enum's variants, so that the method is dispatched straight to the innermost
+/// payload. This only happens if every variant of the nested enum dispatches to its payload, and
+/// the nested enum isn't boxed by `box_above`. Pinned receivers are projected onto the nested enum
+/// instead, since its own pin projection guards can't be relied on.
+pub fn create_match_arms(
+    trait_method: &syn::TraitItemMethod,
+    enum_def: &EnumDispatchItem,
+    remote_trait: Option<&syn::Path>,
+    outer: &dyn Fn(syn::Pat) -> syn::Pat,
+) -> Vec<syn::Arm> {
+    create_match_arms_at(trait_method, enum_def, &enum_def.ident.clone().into(), remote_trait, outer)
+}
+
+/// Creates the match arms for `create_match_arms`, naming the enum by `enum_name`. Nested enums are
+/// named by the path they are written with in the payload type, which may not be in scope as just
+/// the enum's name.
+fn create_match_arms_at(
+    trait_method: &syn::TraitItemMethod,
+    enum_def: &EnumDispatchItem,
+    enum_name: &syn::Path,
+    remote_trait: Option<&syn::Path>,
+    outer: &dyn Fn(syn::Pat) -> syn::Pat,
+) -> Vec<syn::Arm> {
+    let mut match_arms = vec![];
+    for variant in enum_def.variants.iter() {
+        if variant.dispatches() && storage_selector(enum_def, variant).is_none() && !has_pinned_receiver(trait_method) {
+            let nested = nested_enum(enum_def, variant).filter(|nested| nested.variants.iter().all(|variant| variant.dispatches()));
+            if let (Some(nested), Some(nested_path)) = (nested, nested_enum_path(variant)) {
+                match_arms.extend(create_match_arms_at(trait_method, &nested, nested_path, remote_trait, &|pat| {
+                    outer(variant.payload_pattern(enum_name, pat))
+                }));
+                continue;
+            }
+        }
+        let fieldname = syn::Ident::new(FIELDNAME, variant.span());
+        let mut pats = syn::punctuated::Punctuated::new();
+        pats.push(outer(if variant.dispatches() {
+            variant.pattern(enum_name, &fieldname)
+        } else {
+            variant.wildcard_pattern(enum_name)
+        }));
+        match_arms.push(syn::Arm {
+            attrs: vec![],
+            leading_vert: None,
+            pats,
+            guard: None,
+            fat_arrow_token: Default::default(),
+            body: Box::new(match &variant.options.fallback {
+                None => create_trait_fn_call(trait_method, variant, remote_trait),
+                Some(fallback) => create_fallback_expr(fallback, trait_method, &variant.ident),
+            }),
+            comma: Some(Default::default()),
+        });
+    }
+    match_arms
+}
+
 /// Builds an implementation of the given trait function for the given enum type.
-fn create_trait_match(
+pub fn create_trait_match(
     trait_item: syn::TraitItem,
-    enum_name: &syn::Ident,
-    enumvariants: &[&EnumDispatchVariant],
+    enum_def: &EnumDispatchItem,
+    remote_trait: Option<&syn::Path>,
 ) -> syn::ImplItem {
     match trait_item {
-        syn::TraitItem::Method(trait_method) => {
-            let match_expr = create_match_expr(&trait_method, enum_name, enumvariants);
+        syn::TraitItem::Method(mut trait_method) => {
+            let match_expr = create_match_expr(&trait_method, enum_def, remote_trait);
+            let mut attrs = vec![syn::Attribute {
+                pound_token: Default::default(),
+                style: syn::AttrStyle::Outer,
+                bracket_token: Default::default(),
+                path: syn::parse_str("inline").unwrap(),
+                tts: proc_macro::TokenStream::new().into(),
+            }];
+            // Pinned receivers are projected with `unsafe` code, which crates may otherwise deny.
+            if has_pinned_receiver(&trait_method) {
+                attrs.push(syn::parse_quote! { #[allow(unsafe_code)] });
+            }
+            if let Some(syn::FnArg::Captured(syn::ArgCaptured { pat: syn::Pat::Ident(receiver), .. })) =
+                trait_method.sig.decl.inputs.iter_mut().next().filter(|arg| is_pinned_receiver(arg))
+            {
+                receiver.mutability = Some(Default::default());
+            }
 
             syn::ImplItem::Method(syn::ImplItemMethod {
-                attrs: vec![syn::Attribute {
-                    pound_token: Default::default(),
-                    style: syn::AttrStyle::Outer,
-                    bracket_token: Default::default(),
-                    path: syn::parse_str("inline").unwrap(),
-                    tts: proc_macro::TokenStream::new().into(),
-                }],
+                attrs,
                 vis: syn::Visibility::Inherited,
                 defaultness: None,
                 sig: trait_method.sig,
@@ -0,0 +1,140 @@
+//! Keeps track of the inherent methods and associated constants that `enum_dispatch` generates on
+//! an enum. Their names come from the enum's options and the names of its variants, so they can
+//! collide with one another, like the `as_dyn` accessor of a variant named `Dyn` and the `as_dyn`
+//! method of the `dyn_conversions` option. Such collisions are reported up front, rather than
+//! leaving the compiler to point at the generated code.
+use proc_macro2::Span;
+
+use crate::cache;
+use crate::enum_dispatch_item::EnumDispatchItem;
+
+/// An inherent item generated on an enum.
+pub struct GeneratedItem {
+    pub name: String,
+    /// Where to report a collision with this item.
+    pub span: Span,
+    /// Describes what the item was generated for, like "the `introspection` option".
+    pub origin: String,
+}
+
+impl GeneratedItem {
+    pub fn new(name: impl Into<String>, span: Span, origin: impl Into<String>) -> Self {
+        GeneratedItem {
+            name: name.into(),
+            span,
+            origin: origin.into(),
+        }
+    }
+}
+
+/// Lists the inherent items generated on the enum when it is registered. Items generated for
+/// particular variants come last, so that collisions are reported at the variant.
+pub fn enum_items(enum_def: &EnumDispatchItem) -> Vec<GeneratedItem> {
+    let options = &enum_def.options;
+    let span = enum_def.ident.span();
+    let mut items = vec![];
+    let mut push_all = |names: &[&str], origin: &str| {
+        items.extend(names.iter().map(|name| GeneratedItem::new(*name, span, origin)));
+    };
+    if options.dyn_conversions {
+        push_all(&["as_dyn", "as_dyn_mut", "into_box_dyn"], "the `dyn_conversions` option");
+    }
+    if options.variant_trait.is_some() {
+        push_all(&["get", "get_mut", "try_into_variant", "contains_type"], "the `variant_trait` option");
+    }
+    if options.introspection {
+        push_all(
+            &["VARIANT_COUNT", "VARIANT_NAMES", "variant_name", "variant_index", "variant_type_name"],
+            "the `introspection` option",
+        );
+    }
+    if options.any_conversions {
+        push_all(&["try_from_any", "try_from_any_ref"], "the `any_conversions` option");
+    }
+    if options.kind.is_some() {
+        push_all(&["kind"], "the `kind` option");
+    }
+    if let Some(from_name) = &options.from_name {
+        push_all(&["registered_names", "from_name"], "the `from_name` option");
+        if from_name.args.is_some() {
+            push_all(&["from_name_with"], "the `from_name` option");
+        }
+    }
+    if options.box_above.is_some() || options.max_size.is_some() {
+        push_all(&["ENUM_SIZE"], "the `box_above` and `max_size` options");
+    }
+    if options.visitor.is_some() {
+        push_all(&["accept", "accept_mut", "accept_owned"], "the `visitor` option");
+    }
+    for field in options.fields.iter() {
+        let origin = format!("the shared field `{}`", field.name);
+        items.push(GeneratedItem::new(field.name.to_string(), field.name.span(), origin.as_str()));
+        if !field.read_only {
+            items.push(GeneratedItem::new(format!("{}_mut", field.name), field.name.span(), origin));
+        }
+    }
+    if options.accessors {
+        for variant in enum_def.variants.iter() {
+            let snake_case_name = variant.snake_case_name();
+            let origin = format!("the accessors of variant `{}`", variant.ident);
+            let mut names = vec![format!("is_{}", snake_case_name)];
+            if variant.ty.is_some() {
+                names.push(format!("as_{}", snake_case_name));
+                names.push(format!("as_{}_mut", snake_case_name));
+                names.push(format!("into_{}", snake_case_name));
+            }
+            items.extend(names.into_iter().map(|name| GeneratedItem::new(name, variant.ident.span(), origin.as_str())));
+        }
+    }
+    items
+}
+
+/// Lists the inherent methods generated for the traits linked with `inherent` or `partial`, given
+/// the definition of the trait with cache key `key`. The methods of the other such traits that are
+/// already known come first, so that collisions are reported at this trait.
+pub fn linked_trait_items(enum_def: &EnumDispatchItem, traitdef: &syn::ItemTrait, key: &str) -> Vec<GeneratedItem> {
+    let options = &enum_def.options;
+    let mut other_keys: Vec<String> = options.inherent.iter().chain(options.partial.iter()).map(cache::path_key).collect();
+    other_keys.sort();
+    other_keys.dedup();
+    let other_traits = other_keys
+        .into_iter()
+        .filter(|other_key| other_key != key)
+        .filter_map(|other_key| Some((cache::cached_trait(&other_key)?, other_key)));
+    let mut items = vec![];
+    for (traitdef, key) in other_traits.chain(std::iter::once((traitdef.clone(), key.to_string()))) {
+        let methods = traitdef.items.iter().filter_map(|item| match item {
+            syn::TraitItem::Method(method) => Some(&method.sig.ident),
+            _ => None,
+        });
+        for name in methods {
+            if options.is_inherent(&key) {
+                let origin = format!("the inherent methods of `{}`", key);
+                items.push(GeneratedItem::new(name.to_string(), name.span(), origin));
+            }
+            if options.is_partial(&key) {
+                let origin = format!("the `partial` methods of `{}`", key);
+                items.push(GeneratedItem::new(format!("try_{}", name), name.span(), origin));
+            }
+        }
+    }
+    items
+}
+
+/// Returns an error if any two of the items generated on the enum share a name, including the
+/// `additional` items generated when a trait is linked to it.
+pub fn check_collisions(enum_def: &EnumDispatchItem, additional: Vec<GeneratedItem>) -> syn::Result<()> {
+    let items: Vec<GeneratedItem> = enum_items(enum_def).into_iter().chain(additional).collect();
+    for (i, item) in items.iter().enumerate() {
+        if let Some(previous) = items.iter().take(i).find(|previous| previous.name == item.name) {
+            return Err(syn::Error::new(
+                item.span,
+                format!(
+                    "enum_dispatch would generate `{}` on `{}` for both {} and {}",
+                    item.name, enum_def.ident, previous.origin, item.origin
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
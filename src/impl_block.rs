@@ -0,0 +1,184 @@
+//! Implements signature-only impl blocks, which dispatch inherent methods of the enum to
+//! same-named inherent methods of the payloads, without a trait in between:
+//!
+//! ```text
+//! #[enum_dispatch(Knob)]
+//! impl Knob {
+//!     pub fn id(&self) -> u64;
+//! }
+//! ```
+//!
+//! The block is registered like a trait, so that it can appear before or after the enum. Its
+//! methods are cached as a hidden trait marked with an `#[enum_dispatch_inherent(...)]` attribute
+//! holding the block's self type, and the visibility of each method as an
+//! `#[enum_dispatch_vis(...)]` attribute, since trait methods can't have one. The block's generics
+//! are kept as the hidden trait's. Nothing is left in place of the block itself.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+use crate::enum_dispatch_arg_list::EnumDispatchArgList;
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::expansion::create_trait_match;
+use crate::generated_items::{check_collisions, GeneratedItem};
+
+/// Name of the attribute marking the cached form of a signature-only impl block.
+const INHERENT_ATTR: &str = "enum_dispatch_inherent";
+/// Name of the attribute recording the visibility of each method in the cached form.
+const VIS_ATTR: &str = "enum_dispatch_vis";
+
+/// A block like `impl Knob { pub fn id(&self) -> u64; }`, whose methods have no bodies.
+pub struct ImplBlock {
+    pub generics: syn::Generics,
+    pub self_ty: syn::Type,
+    pub methods: Vec<(syn::Visibility, syn::TraitItemMethod)>,
+}
+
+impl syn::parse::Parse for ImplBlock {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        input.call(syn::Attribute::parse_outer)?;
+        input.parse::<syn::Token![impl]>()?;
+        let mut generics: syn::Generics = input.parse()?;
+        let self_ty: syn::Type = input.parse()?;
+        if input.peek(syn::Token![where]) {
+            generics.where_clause = Some(input.parse()?);
+        }
+        if self_type_name(&self_ty).is_none() {
+            return Err(syn::Error::new(self_ty.span(), "Expected the name of an enum_dispatch enum"));
+        }
+        let content;
+        syn::braced!(content in input);
+        let mut methods = vec![];
+        while !content.is_empty() {
+            let attrs = content.call(syn::Attribute::parse_outer)?;
+            let vis: syn::Visibility = content.parse()?;
+            let mut method: syn::TraitItemMethod = content.parse()?;
+            if let Some(block) = &method.default {
+                return Err(syn::Error::new(
+                    block.span(),
+                    "Methods of an enum_dispatch impl block are dispatched to the payloads, so they can't have a body",
+                ));
+            }
+            method.attrs = attrs;
+            methods.push((vis, method));
+        }
+        Ok(ImplBlock { generics, self_ty, methods })
+    }
+}
+
+impl ImplBlock {
+    /// Returns the hidden trait to cache in place of the block. It's named after the enum and the
+    /// block's methods, since an enum may have several signature-only impl blocks, whose methods
+    /// must differ anyway.
+    pub fn to_cached(&self) -> syn::ItemTrait {
+        let mut name = format!("__EnumDispatchInherent_{}", self_type_name(&self.self_ty).unwrap());
+        for (_, method) in self.methods.iter() {
+            name.push('_');
+            name.push_str(&method.sig.ident.to_string());
+        }
+        let ident = syn::Ident::new(&name, proc_macro2::Span::call_site());
+        let methods = self.methods.iter().map(|(vis, method)| {
+            let mut method = method.clone();
+            method.attrs.push(syn::parse_quote! { #[enum_dispatch_vis(#vis)] });
+            method
+        });
+        let self_ty = &self.self_ty;
+        let generics = &self.generics;
+        let where_clause = &self.generics.where_clause;
+        syn::parse_quote! {
+            #[enum_dispatch_inherent(#self_ty)]
+            trait #ident #generics #where_clause {
+                #(#methods)*
+            }
+        }
+    }
+}
+
+/// Returns the name of the enum a block's self type refers to, like `Knob` for `Knob<T>`.
+fn self_type_name(self_ty: &syn::Type) -> Option<&syn::Ident> {
+    match self_ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.iter().last().map(|segment| &segment.ident),
+        _ => None,
+    }
+}
+
+/// Returns the self type recorded in the hidden form of a signature-only impl block.
+fn cached_self_type(traitdef: &syn::ItemTrait) -> syn::Type {
+    let attr = traitdef.attrs.iter().find(|attr| attr.path.is_ident(INHERENT_ATTR)).unwrap();
+    match attr.tts.clone().into_iter().next() {
+        Some(proc_macro2::TokenTree::Group(group)) => syn::parse2(group.stream()).unwrap(),
+        _ => unreachable!(),
+    }
+}
+
+/// Checks whether a cached trait definition is the hidden form of a signature-only impl block.
+pub fn is_impl_block(traitdef: &syn::ItemTrait) -> bool {
+    traitdef.attrs.iter().any(|attr| attr.path.is_ident(INHERENT_ATTR))
+}
+
+/// Checks that a signature-only impl block is linked to the enum it's written for, and only to
+/// that enum, like `#[enum_dispatch(Knob)] impl Knob { ... }`.
+pub fn check_links(traitdef: &syn::ItemTrait, arg_lists: &[EnumDispatchArgList]) -> syn::Result<()> {
+    let self_ty = cached_self_type(traitdef);
+    let self_name = self_type_name(&self_ty).unwrap();
+    if arg_lists.iter().all(|args| args.args.is_empty()) {
+        return Err(syn::Error::new(
+            self_ty.span(),
+            format!("Impl blocks must be linked to their enum, like `#[enum_dispatch({})]`", self_name),
+        ));
+    }
+    for arg in arg_lists.iter().flat_map(|args| args.args.iter()) {
+        let link_name = arg.link_name()?;
+        if self_name != link_name.rsplit("::").next().unwrap_or(&link_name) {
+            return Err(syn::Error::new(
+                self_ty.span(),
+                format!("This impl block is for `{}`, but it's linked to `{}`", self_name, link_name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Generates the inherent impl for the enum from the hidden form of a signature-only impl block.
+/// Each method matches on the enum and calls the payload's method of the same name. The impl
+/// uses the block's generics and self type, so it can be written for some of the enum's types.
+pub fn generate_impl_block(enum_def: &EnumDispatchItem, traitdef: syn::ItemTrait) -> TokenStream {
+    let self_ty = cached_self_type(&traitdef);
+    let methods: Vec<GeneratedItem> = traitdef
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::TraitItem::Method(method) => {
+                Some(GeneratedItem::new(method.sig.ident.to_string(), method.sig.ident.span(), "the signature-only impl block"))
+            }
+            _ => None,
+        })
+        .collect();
+    if let Err(e) = check_collisions(enum_def, methods) {
+        return e.to_compile_error();
+    }
+    let (impl_generics, _, where_clause) = traitdef.generics.split_for_impl();
+    let mut methods = TokenStream::new();
+    for item in traitdef.items {
+        let mut method = match item {
+            syn::TraitItem::Method(method) => method,
+            _ => continue,
+        };
+        let position = method.attrs.iter().position(|attr| attr.path.is_ident(VIS_ATTR)).unwrap();
+        let vis: syn::Visibility = match method.attrs.remove(position).tts.into_iter().next() {
+            Some(proc_macro2::TokenTree::Group(group)) => syn::parse2(group.stream()).unwrap(),
+            _ => syn::Visibility::Inherited,
+        };
+        let attrs = method.attrs.clone();
+        if let syn::ImplItem::Method(mut impl_method) = create_trait_match(syn::TraitItem::Method(method), enum_def, None) {
+            impl_method.attrs.extend(attrs);
+            impl_method.vis = vis;
+            impl_method.to_tokens(&mut methods);
+        }
+    }
+    quote! {
+        impl #impl_generics #self_ty #where_clause {
+            #methods
+        }
+    }
+}
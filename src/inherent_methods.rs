@@ -0,0 +1,74 @@
+//! Implements the `inherent` flag of an enum's `enum_dispatch` attribute, like
+//! `#[enum_dispatch(KnobControl, inherent)]`. Each trait linked in that attribute gets an inherent
+//! method on the enum for each of its methods, which calls the trait method through the trait's
+//! impl for the enum. Callers can then use the methods without importing the trait.
+//!
+//! Inherent methods take precedence over trait methods, so the trait's methods stay callable
+//! without ambiguity when it is in scope. They can't share a name with the other inherent methods
+//! generated on the enum, including those mirroring other traits.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::generated_items::{check_collisions, linked_trait_items};
+
+/// Generates the inherent methods mirroring a trait's methods, given the impl generics of the
+/// trait's impl for the enum. The trait is identified in the cache by `key`.
+pub fn generate_inherent_methods(
+    enum_def: &EnumDispatchItem,
+    traitdef: &syn::ItemTrait,
+    key: &str,
+    trait_path: &syn::Path,
+    generics: &syn::Generics,
+) -> TokenStream {
+    // The trait's generic parameters and associated types can't be named by an inherent method.
+    if !traitdef.generics.params.is_empty() {
+        return syn::Error::new(traitdef.ident.span(), "Traits with generic parameters can't be given inherent methods")
+            .to_compile_error();
+    }
+    if let Some(assoc) = traitdef.items.iter().find(|item| matches!(item, syn::TraitItem::Type(_))) {
+        return syn::Error::new(assoc.span(), "Traits with associated types can't be given inherent methods").to_compile_error();
+    }
+
+    if let Err(e) = check_collisions(enum_def, linked_trait_items(enum_def, traitdef, key)) {
+        return e.to_compile_error();
+    }
+
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = enum_def.generics.split_for_impl();
+    let mut methods = TokenStream::new();
+    for item in traitdef.items.iter() {
+        let method = match item {
+            syn::TraitItem::Method(method) => method,
+            _ => continue,
+        };
+        let name = &method.sig.ident;
+        let docs = method.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+        let mut args = vec![];
+        for arg in method.sig.decl.inputs.iter() {
+            match arg {
+                syn::FnArg::SelfRef(_) | syn::FnArg::SelfValue(_) => args.push(quote! { self }),
+                syn::FnArg::Captured(syn::ArgCaptured { pat: syn::Pat::Ident(pat), .. }) => {
+                    args.push(pat.ident.to_owned().into_token_stream())
+                }
+                _ => return syn::Error::new(arg.span(), "Unsupported argument type").to_compile_error(),
+            }
+        }
+        let sig = &method.sig;
+        methods.extend(quote! {
+            #(#docs)*
+            #[inline]
+            #vis #sig {
+                <Self as #trait_path>::#name(#(#args),*)
+            }
+        });
+    }
+    quote! {
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            #methods
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Implements the `json_schema` enum option, available with the `schemars` feature, which
+//! implements `schemars::JsonSchema` for the enum.
+//!
+//! The schema is a `oneOf` of the payloads' schemas, matching the representation selected by the
+//! `serde` option, including renamed variants. Without it, serde's default representation is
+//! assumed, as for a derived impl. Like the `serde` option, the impl goes through a hidden mirror
+//! enum deriving `JsonSchema`, so the schema always follows the enum's variants.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::expansion::mentions_idents;
+
+/// Generates the `JsonSchema` impl for the enum, along with the mirror enum it delegates to.
+/// Nothing is generated unless the enum uses the `json_schema` option.
+pub fn generate_json_schema(enum_def: &EnumDispatchItem) -> TokenStream {
+    if !enum_def.options.json_schema {
+        return TokenStream::new();
+    }
+    let enumname = &enum_def.ident;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let params: Vec<&syn::Ident> = enum_def.generics.type_params().map(|param| &param.ident).collect();
+    let serde = &enum_def.options.serde;
+    let container_attr = serde.as_ref().map(|repr| repr.container_attr());
+
+    // Generic enums are named like `Knob_for_LinearKnob`, as schemars does for derived impls.
+    let mut schema_name = enumname.to_string();
+    if !params.is_empty() {
+        let params: Vec<String> = params.iter().map(|param| format!("{{{}}}", param)).collect();
+        schema_name = format!("{}_for_{}", schema_name, params.join("_and_"));
+    }
+
+    let mut variants = vec![];
+    let mut impl_generics_bounded = enum_def.generics.clone();
+    for variant in enum_def.variants.iter() {
+        let name = &variant.ident;
+        let rename = variant.options.rename.iter().filter(|_| serde.is_some());
+        variants.push(match &variant.ty {
+            Some(ty) => quote! { #(#[serde(rename = #rename)])* #name(#ty) },
+            None => quote! { #(#[serde(rename = #rename)])* #name },
+        });
+        if let Some(ty) = &variant.ty {
+            if mentions_idents(ty.into_token_stream(), &params) {
+                impl_generics_bounded.make_where_clause().predicates.push(syn::parse_quote! { #ty: ::schemars::JsonSchema });
+            }
+        }
+    }
+    let bounded_where_clause = &impl_generics_bounded.where_clause;
+
+    quote! {
+        const _: () = {
+            #[derive(::schemars::JsonSchema)]
+            #[schemars(rename = #schema_name)]
+            #container_attr
+            #[allow(dead_code)]
+            enum __EnumDispatchSchema #impl_generics #where_clause {
+                #(#variants,)*
+            }
+
+            impl #impl_generics ::schemars::JsonSchema for #enumname #ty_generics #bounded_where_clause {
+                fn inline_schema() -> bool {
+                    <__EnumDispatchSchema #ty_generics as ::schemars::JsonSchema>::inline_schema()
+                }
+
+                fn schema_name() -> ::std::borrow::Cow<'static, str> {
+                    <__EnumDispatchSchema #ty_generics as ::schemars::JsonSchema>::schema_name()
+                }
+
+                fn schema_id() -> ::std::borrow::Cow<'static, str> {
+                    <__EnumDispatchSchema #ty_generics as ::schemars::JsonSchema>::schema_id()
+                }
+
+                fn json_schema(generator: &mut ::schemars::SchemaGenerator) -> ::schemars::Schema {
+                    <__EnumDispatchSchema #ty_generics as ::schemars::JsonSchema>::json_schema(generator)
+                }
+            }
+        };
+    }
+}
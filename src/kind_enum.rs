@@ -0,0 +1,171 @@
+//! Implements the `kind = Name` enum option, which generates a fieldless enum with one variant for
+//! each variant of the `enum_dispatch` enum. Kinds are `Copy`, can be compared, hashed, and
+//! sorted, and convert to and from the variant names, which makes them suitable as keys in
+//! configuration files or labels for metrics.
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::enum_dispatch_variant::EnumDispatchVariant;
+use crate::variant_storage::store_stmt;
+
+/// Generates the kind enum along with its impls, and the `kind` method of the `enum_dispatch`
+/// enum. Nothing is generated unless the enum uses the `kind` option.
+pub fn generate_kind_enum(enum_def: &EnumDispatchItem) -> TokenStream {
+    let kind = match &enum_def.options.kind {
+        Some(kind) => kind,
+        None => return TokenStream::new(),
+    };
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let error = syn::Ident::new(&format!("Parse{}Error", kind), kind.span());
+    let variant_count = enum_def.variants.len();
+    let variant_names: Vec<&syn::Ident> = enum_def.variants.iter().map(|variant| &variant.ident).collect();
+    let variant_names = &variant_names;
+    let name_strings: Vec<String> = variant_names.iter().map(|name| name.to_string()).collect();
+    let name_strings = &name_strings;
+    let kind_paths: Vec<TokenStream> = variant_names.iter().map(|name| quote! { #kind::#name }).collect();
+    let kind_paths = &kind_paths;
+    let patterns = enum_def.variants.iter().map(|variant| variant.wildcard_pattern(enumname));
+    let kind_doc = format!("The kinds of variants of [`{}`], without their payloads.", enumname);
+    let error_doc = format!("The error returned when parsing a [`{}`] from an unknown variant name.", kind);
+    let error_message = format!("unknown {} `{{}}`", kind);
+
+    let mut items = quote! {
+        #[doc = #kind_doc]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #vis enum #kind {
+            #(#variant_names,)*
+        }
+
+        #[allow(dead_code)]
+        impl #kind {
+            /// Every kind, in declaration order.
+            #vis const ALL: [#kind; #variant_count] = [#(#kind_paths),*];
+        }
+
+        impl ::std::fmt::Display for #kind {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(match self {
+                    #(#kind_paths => #name_strings,)*
+                })
+            }
+        }
+
+        #[doc = #error_doc]
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        #vis struct #error(::std::string::String);
+
+        impl ::std::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, #error_message, self.0)
+            }
+        }
+
+        impl ::std::error::Error for #error {}
+    };
+    items.extend(quote! {
+        impl ::std::str::FromStr for #kind {
+            type Err = #error;
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                match s {
+                    #(#name_strings => ::std::result::Result::Ok(#kind_paths),)*
+                    _ => ::std::result::Result::Err(#error(::std::string::String::from(s))),
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            /// Returns the kind of the current variant.
+            #[inline]
+            #vis fn kind(&self) -> #kind {
+                match self {
+                    #(#patterns => #kind_paths,)*
+                }
+            }
+        }
+    });
+    if enum_def.generics.params.is_empty() {
+        items.extend(generate_default_value(enum_def, kind));
+    }
+    items
+}
+
+/// Generates `default_value`, which creates a value of the enum for a kind. This is only generated
+/// for non-generic enums, since the kind enum itself isn't generic.
+///
+/// The macro can't tell which payload types implement `Default`, so the decision is left to method
+/// resolution: a probe method implemented for all `T: Default` is preferred over one implemented
+/// for references to any `T`, which makes `default_value` return `None` for that kind.
+fn generate_default_value(enum_def: &EnumDispatchItem, kind: &syn::Ident) -> TokenStream {
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let arms = enum_def.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        let value = default_value_expr(enum_def, variant);
+        quote! { #kind::#variant_name => #value, }
+    });
+    let probe = default_probe();
+    quote! {
+        #[allow(dead_code)]
+        impl #kind {
+            /// Creates a value of the enum with this kind, using `Default::default()` for the
+            /// payload. Returns `None` if the payload type of this kind doesn't implement
+            /// `Default`.
+            #vis fn default_value(self) -> ::std::option::Option<#enumname> {
+                #probe
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
+
+/// Defines the `Probe` type used by `default_value_expr` to find out whether a payload type
+/// implements `Default`. It must be in scope wherever those expressions are used.
+pub fn default_probe() -> TokenStream {
+    quote! {
+        struct Probe<T>(::std::marker::PhantomData<T>);
+        trait ViaDefault<T> {
+            fn probe(&self) -> ::std::option::Option<T>;
+        }
+        impl<T: ::std::default::Default> ViaDefault<T> for Probe<T> {
+            fn probe(&self) -> ::std::option::Option<T> {
+                ::std::option::Option::Some(T::default())
+            }
+        }
+        trait WithoutDefault<T> {
+            fn probe(&self) -> ::std::option::Option<T>;
+        }
+        impl<T> WithoutDefault<T> for &Probe<T> {
+            fn probe(&self) -> ::std::option::Option<T> {
+                ::std::option::Option::None
+            }
+        }
+    }
+}
+
+/// Creates an expression for the default value of a single variant, as an `Option` of the enum.
+pub fn default_value_expr(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant) -> TokenStream {
+    let enumname = &enum_def.ident;
+    let variant_name = &variant.ident;
+    if let syn::Fields::Unit = variant.fields {
+        return quote! { ::std::option::Option::Some(#enumname::#variant_name) };
+    }
+    let value = syn::Ident::new("value", proc_macro2::Span::call_site());
+    match (&variant.ty, variant.constructor(enumname, &value)) {
+        (Some(ty), Some(constructor)) => {
+            let store = store_stmt(enum_def, variant, &value);
+            quote! {
+                (&Probe::<#ty>(::std::marker::PhantomData)).probe().map(|#value| {
+                    #store
+                    #constructor
+                })
+            }
+        }
+        _ => quote! { ::std::option::Option::None },
+    }
+}
@@ -319,16 +319,16 @@
 
 extern crate proc_macro;
 
-use proc_macro::{
-    TokenStream,
-    TokenTree
-};
+use proc_macro::TokenStream;
 use quote::{ToTokens, TokenStreamExt};
+use syn::parse::Parser;
 
 /// Used for converting a macro input into an ItemTrait or an EnumDispatchItem.
 mod attributed_parser;
 /// Provides local storage for enum and trait definitions so that they can be accessed later.
 mod cache;
+/// Provides a parser for the arguments of `enum_dispatch` attributes.
+mod enum_dispatch_arg_list;
 /// Provides a custom syntax specification for enum dispatch syntax blocks.
 mod enum_dispatch_item;
 /// Provides a custom syntax specification for the variants of enum dispatch syntax blocks.
@@ -337,8 +337,43 @@ mod enum_dispatch_variant;
 mod expansion;
 /// Convenience trait for token parsing.
 mod filter_attrs;
+/// Lists the inherent items generated on enums, reporting any whose names collide.
+mod generated_items;
+/// Parses and registers signature-only impl blocks, dispatching inherent methods to the payloads.
+mod impl_block;
+/// Generates inherent methods mirroring the methods of traits linked with `inherent`.
+mod inherent_methods;
+/// Implements `schemars::JsonSchema` for enums using the `json_schema` option.
+mod json_schema;
+/// Generates the fieldless enum of variant kinds requested by the `kind` option.
+mod kind_enum;
+/// Generates methods creating variants from their names, requested by the `from_name` option.
+mod name_registry;
+/// Dispatches traits linked with `partial` to the variants implementing them.
+mod partial_dispatch;
+/// Recognizes smart pointer and lock types wrapping variant payloads.
+mod payload_wrapper;
+/// Projects pinned enums onto their payloads for methods like `Future::poll`.
+mod pin_projection;
+/// Parses and registers the signatures of traits declared with `remote_trait!`.
+mod remote_trait;
+/// Implements serde's traits for enums using the `serde` option.
+mod serde_repr;
+/// Generates accessors for fields shared by every payload, requested by the `fields` option.
+mod shared_fields;
+/// Declares the standard library traits that enums can link to without registering them.
+mod std_traits;
+/// Generates conversions to and from a narrower enum, requested by the `superset_of` option.
+mod superset;
+/// Decides whether variant payloads are stored inline or boxed, based on their size.
+mod variant_storage;
+/// Generates visitor traits and a `match` macro for running generic code on variant payloads.
+mod visitor;
 
-use crate::expansion::add_enum_impls;
+use crate::enum_dispatch_arg_list::{is_enum_dispatch_attr, EnumDispatchArgList};
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::expansion::{add_enum_conversions, add_enum_impls};
+use crate::remote_trait::{linked_trait_bound, RemoteTraits};
 
 /// Annotating a trait or enum definition with an `#[enum_dispatch]` attribute will register it
 /// with the enum_dispatch library, allowing it to be used to generate impl blocks elsewhere.
@@ -352,17 +387,118 @@ use crate::expansion::add_enum_impls;
 /// An annotated enum should have variants that are simply the names of types imported to the
 /// current scope. To force individual variants to use a custom name when expanded, each variant
 /// can also take the form of a normal tuple-style enum variant with a single field.
+/// Variants with several fields mark the one to dispatch to with `#[dispatch]`.
+///
+/// Enums also accept options alongside the names of linked traits, like
+/// `#[enum_dispatch(KnobControl, box_above = 64)]`, and variants accept their own in an
+/// `#[enum_dispatch(...)]` attribute. The options are described in the README. Lowercase names
+/// are always taken for options, so a misspelled one is an error rather than a missing trait:
+///
+/// ```compile_fail
+/// # use enum_dispatch::enum_dispatch;
+/// # #[enum_dispatch]
+/// # trait Sink {
+/// #     fn write(&mut self, data: &str) -> usize;
+/// # }
+/// # struct FileSink;
+/// # impl Sink for FileSink {
+/// #     fn write(&mut self, data: &str) -> usize { data.len() }
+/// # }
+/// #[enum_dispatch(Sink, acessors)]
+/// enum AnySink {
+///     FileSink,
+/// }
+/// ```
+///
+/// The methods generated by different options must not share a name, like the `as_dyn` method of
+/// `dyn_conversions` and the `as_dyn` accessor of a variant named `Dyn`:
+///
+/// ```compile_fail
+/// # use enum_dispatch::enum_dispatch;
+/// # #[enum_dispatch]
+/// # trait Sink {
+/// #     fn write(&mut self, data: &str) -> usize;
+/// # }
+/// # struct FileSink;
+/// # impl Sink for FileSink {
+/// #     fn write(&mut self, data: &str) -> usize { data.len() }
+/// # }
+/// #[enum_dispatch(Sink, dyn_conversions, accessors)]
+/// enum AnySink {
+///     FileSink,
+///     Dyn(Box<dyn Sink>),
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn enum_dispatch(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let new_block = attributed_parser::parse_attributed(item.clone()).unwrap();
+    let mut new_block = match attributed_parser::parse_attributed(item.clone()) {
+        Ok(new_block) => new_block,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut arg_lists: Vec<EnumDispatchArgList> = match syn::parse(attr) {
+        Ok(args) => vec![args],
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // Additional `enum_dispatch` attributes on an enum are processed here as well, since the
+    // expanded enum no longer contains the `enum_dispatch`-specific syntax needed to parse it again.
+    if let attributed_parser::ParsedItem::EnumDispatch(enumdef) = &mut new_block {
+        let mut remaining_attrs = vec![];
+        for attr in enumdef.attrs.drain(..) {
+            if is_enum_dispatch_attr(&attr) {
+                match EnumDispatchArgList::from_attr(&attr) {
+                    Ok(args) => arg_lists.push(args),
+                    Err(e) => return e.to_compile_error().into(),
+                }
+            } else {
+                remaining_attrs.push(attr);
+            }
+        }
+        enumdef.attrs = remaining_attrs;
+        for args in arg_lists.iter_mut() {
+            let mut links = syn::punctuated::Punctuated::new();
+            for arg in args.args.iter() {
+                match enumdef.options.apply(arg) {
+                    Ok(true) => (),
+                    Ok(false) => links.push(arg.clone()),
+                    Err(e) => return e.to_compile_error().into(),
+                }
+            }
+            enumdef.options.mark_links(args, &links.iter().cloned().collect::<Vec<_>>());
+            // Pin projection guards are generated with the enum, so they're requested here for
+            // any linked trait that's already known.
+            let linked_traits = links.iter().filter_map(|link| cache::cached_trait(&link.link_name().ok()?));
+            if linked_traits.collect::<Vec<_>>().iter().any(pin_projection::has_pinned_method) {
+                enumdef.options.pinned = true;
+            }
+            args.args = links;
+        }
+    }
     let expanded = match &new_block {
         attributed_parser::ParsedItem::Trait(traitdef) => {
+            if impl_block::is_impl_block(traitdef) {
+                if let Err(e) = impl_block::check_links(traitdef, &arg_lists) {
+                    return e.to_compile_error().into();
+                }
+            }
             cache::cache_trait(traitdef.to_owned());
-            item
+            // Signature-only impl blocks are replaced by the impls generated for them.
+            if impl_block::is_impl_block(traitdef) {
+                TokenStream::new()
+            } else {
+                item
+            }
         }
         attributed_parser::ParsedItem::EnumDispatch(enumdef) => {
             cache::cache_enum_dispatch(enumdef.clone());
-            syn::ItemEnum::from(enumdef.to_owned()).into_token_stream().into()
+            let mut expanded = syn::ItemEnum::from(enumdef.to_owned()).into_token_stream();
+            let bounds: Vec<proc_macro2::TokenStream> = arg_lists
+                .iter()
+                .flat_map(|args| args.args.iter())
+                .filter(|arg| arg.link_name().map_or(true, |key| !enumdef.options.is_partial(&key)))
+                .filter_map(|arg| linked_trait_bound(arg, &enumdef.generics))
+                .collect();
+            expanded.append_all(add_enum_conversions(enumdef, &bounds));
+            expanded.into()
         }
     };
     let mut expanded = proc_macro2::TokenStream::from(expanded);
@@ -370,39 +506,166 @@ pub fn enum_dispatch(attr: TokenStream, item: TokenStream) -> TokenStream {
     // That definition may or may not be cached yet.
     // If it's not cached yet, we will have to push the link into the cache and defer impl
     // generation until the missing definition is encountered.
-    // For now, we assume it is already cached.
-    if !attr.is_empty() {
-        let mut attr = attr.into_iter();
-        let attr_name = match attr.next().unwrap() {
-            TokenTree::Ident(i) => i,
-            _ => panic!("Must specify an identifier for a trait"),
-        };
-        if attr.count() != 0 {
-            panic!("Too many arguments to enum_dispatch");
-        }
-        match &new_block {
-            attributed_parser::ParsedItem::Trait(traitdef) => cache::defer_link(&attr_name, &traitdef.ident),
-            attributed_parser::ParsedItem::EnumDispatch(enumdef) => cache::defer_link(&attr_name, &enumdef.ident),
+    for args in arg_lists {
+        for arg in args.args.iter() {
+            let link_name = match arg.link_name() {
+                Ok(link_name) => link_name,
+                Err(e) => return e.to_compile_error().into(),
+            };
+            match &new_block {
+                attributed_parser::ParsedItem::Trait(traitdef) => cache::defer_link(&link_name, &traitdef.ident.to_string()),
+                attributed_parser::ParsedItem::EnumDispatch(enumdef) => cache::defer_link(&link_name, &enumdef.ident.to_string()),
+            }
         }
-    };
+    }
     // It would be much simpler to just always retrieve both definitions from the cache.
     // However, span information is not stored in the cache. Saving the newly retrieved
     // definition prevents *all* of the span information from being lost.
     match new_block.clone() {
         attributed_parser::ParsedItem::Trait(traitdef) => {
-            let additional_enums = cache::fulfilled_by_trait(&traitdef.ident);
+            let additional_enums = cache::fulfilled_by_trait(&traitdef.ident.to_string());
             for enumdef in additional_enums {
-                cache::remove_entry(&enumdef.ident);
+                cache::remove_entry(&enumdef.ident.to_string());
                 expanded.append_all(add_enum_impls(enumdef, traitdef.clone()));
             }
         },
         attributed_parser::ParsedItem::EnumDispatch(enumdef) => {
-            let additional_traits = cache::fulfilled_by_enum(&enumdef.ident);
-            for traitdef in additional_traits {
-                cache::remove_entry(&traitdef.ident);
+            let additional_traits = cache::fulfilled_by_enum(&enumdef.ident.to_string());
+            for (link_name, traitdef) in additional_traits {
+                cache::remove_entry(&link_name);
                 expanded.append_all(add_enum_impls(enumdef.clone(), traitdef));
             }
         },
     }
     expanded.into()
 }
+
+/// Declares an `enum_dispatch` enum whose variants may be written as arbitrary types, including
+/// path-qualified and generic types that can't be parsed as plain enum variants.
+///
+/// Each variant specified as a bare type is named after that type: `shapes::Circle` becomes
+/// `Circle`, `Wrapper<u8>` becomes `WrapperU8`, and `Vec<Foo>` becomes `VecFoo`. Variants can
+/// still be named explicitly with the usual `Name(Type)` form, which is required to resolve
+/// collisions between derived names.
+///
+/// The enum is registered as if it had been annotated with `#[enum_dispatch]`, and any
+/// `#[enum_dispatch(...)]` attributes inside the macro are applied as usual.
+///
+/// ```
+/// # use enum_dispatch::{enum_dispatch, enum_dispatch_enum};
+/// #[enum_dispatch]
+/// trait Shaped {
+///     fn area(&self) -> f32;
+/// }
+///
+/// mod shapes {
+///     pub struct Circle { pub r: f32 }
+/// }
+///
+/// struct Wrapper<T>(T);
+///
+/// # impl Shaped for shapes::Circle {
+/// #     fn area(&self) -> f32 { self.r * self.r * 3.14 }
+/// # }
+/// impl<T: Into<f32> + Copy> Shaped for Wrapper<T> {
+///     fn area(&self) -> f32 { self.0.into() }
+/// }
+///
+/// enum_dispatch_enum! {
+///     #[enum_dispatch(Shaped)]
+///     enum Shape {
+///         shapes::Circle,
+///         Wrapper<u8>,
+///         Wrapper<u16>,
+///     }
+/// }
+///
+/// let shape = Shape::WrapperU16(Wrapper(4));
+/// assert_eq!(shape.area(), 4.);
+/// ```
+///
+/// Types from different modules with the same name would both derive the same variant name, so they
+/// are rejected until one of them is renamed:
+///
+/// ```compile_fail
+/// # use enum_dispatch::{enum_dispatch, enum_dispatch_enum};
+/// mod a { pub struct Circle; }
+/// mod b { pub struct Circle; }
+///
+/// enum_dispatch_enum! {
+///     enum Shape {
+///         a::Circle,
+///         b::Circle,
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn enum_dispatch_enum(input: TokenStream) -> TokenStream {
+    let enumdef = match EnumDispatchItem::parse_type_shorthand.parse(input) {
+        Ok(enumdef) => enumdef,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // The expanded variants are all explicitly named, so the enum can be processed as if it had
+    // been annotated with `#[enum_dispatch]`, including any `enum_dispatch` attributes within it.
+    enum_dispatch(TokenStream::new(), enumdef.into_token_stream().into())
+}
+
+/// Registers the signature of a trait that can't be annotated with `#[enum_dispatch]`, such as one
+/// defined in another crate. Enums can then link to the trait using the path it was declared with.
+///
+/// ```
+/// # use enum_dispatch::{enum_dispatch, remote_trait};
+/// mod vendor {
+///     pub trait Handler {
+///         fn handle(&self, request: &str) -> String;
+///     }
+/// }
+///
+/// remote_trait! {
+///     trait crate::vendor::Handler {
+///         fn handle(&self, request: &str) -> String;
+///     }
+/// }
+///
+/// struct Echo;
+/// impl vendor::Handler for Echo {
+///     fn handle(&self, request: &str) -> String { request.to_string() }
+/// }
+/// # struct Shout;
+/// # impl vendor::Handler for Shout {
+/// #     fn handle(&self, request: &str) -> String { request.to_uppercase() }
+/// # }
+///
+/// #[enum_dispatch(crate::vendor::Handler)]
+/// enum AnyHandler {
+///     Echo,
+///     Shout,
+/// }
+///
+/// # fn main() {
+/// use vendor::Handler;
+/// assert_eq!(AnyHandler::from(Echo).handle("hi"), "hi");
+/// # }
+/// ```
+///
+/// Types in the signature are resolved wherever the impl for an enum is generated, which is next to
+/// whichever of the trait declaration and the enum is processed second. Paths that resolve from
+/// anywhere in the crate, like `crate::...` or paths through other crates, are recommended.
+#[proc_macro]
+pub fn remote_trait(input: TokenStream) -> TokenStream {
+    let remote_traits: RemoteTraits = match syn::parse(input) {
+        Ok(remote_traits) => remote_traits,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut expanded = proc_macro2::TokenStream::new();
+    for remote_trait in remote_traits.0 {
+        let key = cache::path_key(&remote_trait.path);
+        let traitdef = remote_trait.to_cached();
+        cache::cache_remote_trait(key.clone(), traitdef.clone());
+        for enumdef in cache::fulfilled_by_trait(&key) {
+            cache::remove_entry(&enumdef.ident.to_string());
+            expanded.append_all(add_enum_impls(enumdef, traitdef.clone()));
+        }
+    }
+    expanded.into()
+}
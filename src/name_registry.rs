@@ -0,0 +1,151 @@
+//! Implements the `from_name` enum option, which generates methods creating a variant of the enum
+//! from its name, such as one read from a configuration file at runtime:
+//!
+//! - `registered_names()` lists the names of every variant, in declaration order.
+//! - `from_name(name)` creates the named variant with a default payload, if its payload type
+//!   implements `Default`.
+//! - `from_name_with(name, args)` is generated for `from_name(args = Type)`. It creates the named
+//!   variant using the function given by the variant's `#[enum_dispatch(constructor = ...)]`
+//!   attribute, which takes the `args` and returns the payload. Variants without a constructor are
+//!   created like in `from_name`.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+use crate::enum_dispatch_arg_list::{EnumDispatchArg, EnumDispatchArgList};
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::expansion::mentions_idents;
+use crate::kind_enum::{default_probe, default_value_expr};
+use crate::variant_storage::store_stmt;
+
+/// Options of the `from_name` enum option.
+#[derive(Clone, Default)]
+pub struct FromName {
+    /// `args = Type`: the type of the arguments passed to variant constructors. Boxed to keep
+    /// the enum's options small.
+    pub args: Option<Box<syn::Type>>,
+}
+
+impl FromName {
+    /// Parses either the `from_name` or the `from_name(args = Type)` form.
+    pub fn from_arg(arg: &EnumDispatchArg) -> syn::parse::Result<Self> {
+        match arg {
+            EnumDispatchArg::Flag(_) => Ok(Self::default()),
+            EnumDispatchArg::List(_, list) => {
+                let mut from_name = Self::default();
+                for arg in syn::parse2::<EnumDispatchArgList>(list.clone())?.args.iter() {
+                    match arg.name().to_string().as_str() {
+                        "args" => from_name.args = Some(Box::new(arg.parse_value()?)),
+                        _ => return Err(arg.unsupported("in the from_name enum_dispatch option")),
+                    }
+                }
+                Ok(from_name)
+            }
+            _ => Err(syn::Error::new(
+                arg.span(),
+                "Expected `from_name` or `from_name(args = Type)` as the enum_dispatch option",
+            )),
+        }
+    }
+}
+
+/// Converts the options back into the `from_name` enum option.
+impl ToTokens for FromName {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match &self.args {
+            Some(args) => quote! { from_name(args = #args) },
+            None => quote! { from_name },
+        });
+    }
+}
+
+/// Generates `registered_names`, `from_name`, and `from_name_with`. Nothing is generated unless
+/// the enum uses the `from_name` option.
+pub fn generate_name_registry(enum_def: &EnumDispatchItem) -> TokenStream {
+    let args_type = match &enum_def.options.from_name {
+        Some(from_name) => from_name.args.as_ref(),
+        None => None,
+    };
+    if args_type.is_none() {
+        if let Some(variant) = enum_def.variants.iter().find(|variant| variant.options.constructor.is_some()) {
+            let constructor = variant.options.constructor.as_ref().unwrap();
+            return syn::Error::new(
+                constructor.span(),
+                "enum_dispatch variant constructors require the `from_name(args = Type)` enum option",
+            )
+            .to_compile_error();
+        }
+    }
+    if enum_def.options.from_name.is_none() {
+        return TokenStream::new();
+    }
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let params: Vec<&syn::Ident> = enum_def.generics.type_params().map(|param| &param.ident).collect();
+    let names: Vec<String> = enum_def.variants.iter().map(|variant| variant.ident.to_string()).collect();
+    let names = &names;
+
+    // Payloads referring to the enum's generic parameters can't be probed for `Default`.
+    let default_values = enum_def.variants.iter().map(|variant| match &variant.ty {
+        Some(ty) if mentions_idents(ty.into_token_stream(), &params) => quote! { ::std::option::Option::None },
+        _ => default_value_expr(enum_def, variant),
+    });
+    let probe = default_probe();
+
+    let mut items = quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            /// Returns the names of every variant, as accepted by `from_name`.
+            #vis fn registered_names() -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+
+            /// Creates the variant with the given name, using `Default::default()` for its
+            /// payload. Returns `None` if there's no such variant, or if its payload type doesn't
+            /// implement `Default`.
+            #vis fn from_name(name: &str) -> ::std::option::Option<Self> {
+                #probe
+                match name {
+                    #(#names => #default_values,)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    };
+
+    if let Some(args_type) = args_type {
+        let value = syn::Ident::new("value", proc_macro2::Span::call_site());
+        let arms = enum_def.variants.iter().filter_map(|variant| {
+            let function = variant.options.constructor.as_ref()?;
+            let name = variant.ident.to_string();
+            let created = match variant.constructor(enumname, &value) {
+                Some(constructor) => {
+                    let store = store_stmt(enum_def, variant, &value);
+                    quote! {
+                        let #value = #function(args);
+                        #store
+                        ::std::option::Option::Some(#constructor)
+                    }
+                }
+                None => quote! { ::std::option::Option::None },
+            };
+            Some(quote! { #name => { #created } })
+        });
+        items.extend(quote! {
+            #[allow(dead_code)]
+            impl #impl_generics #enumname #ty_generics #where_clause {
+                /// Creates the variant with the given name by passing `args` to its constructor.
+                /// Variants without a constructor are created like in `from_name`.
+                #[allow(unused_variables)]
+                #vis fn from_name_with(name: &str, args: #args_type) -> ::std::option::Option<Self> {
+                    match name {
+                        #(#arms)*
+                        _ => Self::from_name(name),
+                    }
+                }
+            }
+        });
+    }
+    items
+}
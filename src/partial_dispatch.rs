@@ -0,0 +1,136 @@
+//! Implements partial links, requested by adding `partial` to an enum's `enum_dispatch` attribute,
+//! like `#[enum_dispatch(Resizable, partial)]`. Every trait linked in that attribute is only
+//! implemented by some of the variants, so the enum can't implement it. Instead, each method
+//! `resize` of the trait gets an inherent counterpart `try_resize`, which returns `Some` with the
+//! result for variants implementing the trait, and `None` for the rest.
+//!
+//! Which variants implement the trait is declared using `#[enum_dispatch(implements(Resizable))]`
+//! on each of them, since a procedural macro can't find out on its own.
+//!
+//! The `try_` methods can't share a name with the other inherent methods generated on the enum.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+use crate::cache;
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::expansion::{create_match_arms, mentions_idents};
+use crate::generated_items::{check_collisions, linked_trait_items};
+use crate::payload_wrapper::unwrap_payload;
+
+/// Parses the parenthesized list of trait paths taken by the `partial(...)` and `implements(...)`
+/// arguments.
+pub fn parse_trait_list(list: &TokenStream) -> syn::parse::Result<Vec<syn::Path>> {
+    let parser = syn::punctuated::Punctuated::<syn::Path, syn::token::Comma>::parse_terminated;
+    Ok(syn::parse::Parser::parse2(parser, list.clone())?.into_iter().collect())
+}
+
+/// Checks that each variant only declares that it implements traits linked with `partial`, which
+/// catches misspelled trait names that would otherwise silently dispatch to nothing.
+pub fn check_implemented_traits(enum_def: &EnumDispatchItem) -> TokenStream {
+    for variant in enum_def.variants.iter() {
+        for path in variant.options.implements.iter() {
+            if !enum_def.options.is_partial(&cache::path_key(path)) {
+                return syn::Error::new(
+                    path.span(),
+                    format!(
+                        "`{}` must be linked to `{}` with `partial` for variants to implement it",
+                        path.into_token_stream(),
+                        enum_def.ident
+                    ),
+                )
+                .to_compile_error();
+            }
+        }
+    }
+    TokenStream::new()
+}
+
+/// Generates the inherent `try_` methods dispatching a partially linked trait to the variants
+/// implementing it.
+pub fn generate_partial_impls(
+    enum_def: &EnumDispatchItem,
+    traitdef: &syn::ItemTrait,
+    traitname: &TokenStream,
+    remote_trait: Option<&syn::Path>,
+) -> TokenStream {
+    if !traitdef.generics.params.is_empty() {
+        return syn::Error::new(traitdef.ident.span(), "Traits linked with `partial` can't have generic parameters").to_compile_error();
+    }
+    let key = match remote_trait {
+        Some(path) => cache::path_key(path),
+        None => traitdef.ident.to_string(),
+    };
+    if let Err(e) = check_collisions(enum_def, linked_trait_items(enum_def, traitdef, &key)) {
+        return e.to_compile_error();
+    }
+    let mut implementing = enum_def.clone();
+    implementing.variants = enum_def
+        .variants
+        .iter()
+        .filter(|variant| variant.options.implements.iter().any(|path| cache::path_key(path) == key))
+        .cloned()
+        .collect();
+
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let mut generics = enum_def.generics.clone();
+    let params: Vec<&syn::Ident> = enum_def.generics.type_params().map(|param| &param.ident).collect();
+    for variant in implementing.variants.iter() {
+        match &variant.ty {
+            Some(ty) if variant.dispatches() && mentions_idents(ty.into_token_stream(), &params) => {
+                let ty = unwrap_payload(ty, &variant.options).1;
+                generics.make_where_clause().predicates.push(syn::parse_quote! { #ty: #traitname });
+            }
+            _ => (),
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut methods = TokenStream::new();
+    for item in traitdef.items.iter() {
+        let method = match item {
+            syn::TraitItem::Method(method) => method,
+            _ => {
+                return syn::Error::new(item.span(), "Traits linked with `partial` can only have methods").to_compile_error();
+            }
+        };
+        let has_receiver = matches!(method.sig.decl.inputs.iter().next(), Some(syn::FnArg::SelfRef(_)) | Some(syn::FnArg::SelfValue(_)));
+        if !has_receiver {
+            return syn::Error::new(
+                method.sig.ident.span(),
+                "Methods of traits linked with `partial` must take `self`, `&self`, or `&mut self`",
+            )
+            .to_compile_error();
+        }
+        let arms = create_match_arms(method, &implementing, remote_trait, &|pat| pat).into_iter().map(|mut arm| {
+            let body = &arm.body;
+            arm.body = Box::new(syn::parse_quote! { ::std::option::Option::Some(#body) });
+            arm
+        });
+        let mut sig = method.sig.clone();
+        let name = &method.sig.ident;
+        sig.ident = syn::Ident::new(&format!("try_{}", name), name.span());
+        sig.decl.output = match &method.sig.decl.output {
+            syn::ReturnType::Default => syn::parse_quote! { -> ::std::option::Option<()> },
+            syn::ReturnType::Type(_, ty) => syn::parse_quote! { -> ::std::option::Option<#ty> },
+        };
+        let doc = format!("Calls `{}::{}` if this variant implements it, or returns `None` otherwise.", key, name);
+        methods.extend(quote! {
+            #[doc = #doc]
+            #[inline]
+            #[allow(unreachable_patterns)]
+            #vis #sig {
+                match self {
+                    #(#arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        });
+    }
+    quote! {
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            #methods
+        }
+    }
+}
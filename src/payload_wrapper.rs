@@ -0,0 +1,193 @@
+//! Recognizes common smart pointer and lock types wrapping the payload of an `enum_dispatch`
+//! variant, so that trait methods can be dispatched to the wrapped value rather than requiring the
+//! wrapper itself to implement the trait.
+//!
+//! Wrappers are recognized by the final segment of their path, so `Arc<T>` and
+//! `std::sync::Arc<T>` are treated identically. Wrappers may be nested, as in `Arc<Mutex<T>>`.
+//!
+//! Pointers are always looked through, but locks and `RefCell`s are only acquired for variants
+//! with a `lock` option, since a type of the same name might not have the expected methods. No
+//! layers at all are looked through for variants with the `no_unwrap` option, which is needed if
+//! the wrapper type itself implements the trait.
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::enum_dispatch_variant::VariantOptions;
+
+/// A single layer of wrapping around a payload type.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Wrapper {
+    Box,
+    Rc,
+    Arc,
+    /// `&T`
+    Ref,
+    /// `&mut T`
+    RefMut,
+    Mutex,
+    RwLock,
+    RefCell,
+}
+
+impl Wrapper {
+    /// Returns `true` if the wrapper can be created from the value it wraps using `new`.
+    fn is_constructible(self) -> bool {
+        !matches!(self, Wrapper::Ref | Wrapper::RefMut)
+    }
+
+    /// Returns `true` if the wrapper must be locked or borrowed to access the value it wraps.
+    pub fn is_lock(self) -> bool {
+        matches!(self, Wrapper::Mutex | Wrapper::RwLock | Wrapper::RefCell)
+    }
+}
+
+/// How the lock of a `Mutex` or `RwLock` payload is acquired, specified per variant using
+/// `#[enum_dispatch(lock = ...)]`. A plain `lock` flag uses the default strategy.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum LockStrategy {
+    /// `lock = panic`: panic if the lock is poisoned. This is the default.
+    #[default]
+    Panic,
+    /// `lock = ignore_poison`: use the value even if the lock is poisoned.
+    IgnorePoison,
+    /// `lock = infallible`: the lock returns its guard directly rather than a `Result`, as is the
+    /// case for locks from crates like `parking_lot`.
+    Infallible,
+}
+
+impl syn::parse::Parse for LockStrategy {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let strategy: syn::Ident = input.parse()?;
+        if strategy == "panic" {
+            Ok(LockStrategy::Panic)
+        } else if strategy == "ignore_poison" {
+            Ok(LockStrategy::IgnorePoison)
+        } else if strategy == "infallible" {
+            Ok(LockStrategy::Infallible)
+        } else {
+            Err(syn::Error::new(
+                strategy.span(),
+                "Expected `panic`, `ignore_poison`, or `infallible` as the enum_dispatch lock strategy",
+            ))
+        }
+    }
+}
+
+/// Describes how the payload is accessed by a trait method's receiver.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Access {
+    /// `&self`
+    Shared,
+    /// `&mut self`
+    Mutable,
+    /// `self`
+    Owned,
+}
+
+/// Splits the payload type of a variant with the given options into its wrapper layers, outermost
+/// first, and the innermost wrapped type. Types that aren't wrapped have no layers.
+pub fn unwrap_payload<'a>(ty: &'a syn::Type, options: &VariantOptions) -> (Vec<(Wrapper, &'a syn::Type)>, &'a syn::Type) {
+    let mut layers = vec![];
+    let mut inner = ty;
+    if options.no_unwrap {
+        return (layers, inner);
+    }
+    while let Some((wrapper, wrapped)) = unwrap_layer(inner) {
+        if wrapper.is_lock() && options.lock.is_none() {
+            break;
+        }
+        layers.push((wrapper, inner));
+        inner = wrapped;
+    }
+    (layers, inner)
+}
+
+/// Recognizes a single wrapper layer, returning the wrapper and the type it wraps.
+fn unwrap_layer(ty: &syn::Type) -> Option<(Wrapper, &syn::Type)> {
+    match ty {
+        syn::Type::Reference(reference) => Some((
+            if reference.mutability.is_some() { Wrapper::RefMut } else { Wrapper::Ref },
+            &reference.elem,
+        )),
+        syn::Type::Paren(paren) => unwrap_layer(&paren.elem),
+        syn::Type::Group(group) => unwrap_layer(&group.elem),
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            let segment = path.segments.iter().last()?;
+            let wrapper = match segment.ident.to_string().as_str() {
+                "Box" => Wrapper::Box,
+                "Rc" => Wrapper::Rc,
+                "Arc" => Wrapper::Arc,
+                "Mutex" => Wrapper::Mutex,
+                "RwLock" => Wrapper::RwLock,
+                "RefCell" => Wrapper::RefCell,
+                _ => return None,
+            };
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+                    match arguments.args.iter().next() {
+                        Some(syn::GenericArgument::Type(wrapped)) => Some((wrapper, wrapped)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Creates an expression for the innermost wrapped value of the payload bound to `binding`,
+/// suitable as the receiver of a trait method call. `binding` should be a reference to the payload
+/// for reference receivers, or the payload itself for owned receivers.
+///
+/// Pointer layers are left to method call autoderef, which still prefers an implementation of the
+/// trait for the pointer type itself, if there is one. Locks are acquired explicitly.
+pub fn access_expr(binding: &syn::Ident, ty: &syn::Type, access: Access, options: &VariantOptions) -> TokenStream {
+    let (layers, _) = unwrap_payload(ty, options);
+    let unlock = match options.lock.unwrap_or_default() {
+        LockStrategy::Panic => quote! { .unwrap() },
+        LockStrategy::IgnorePoison => quote! { .unwrap_or_else(::std::sync::PoisonError::into_inner) },
+        LockStrategy::Infallible => quote! {},
+    };
+    let mut expr = quote! { #binding };
+    for (wrapper, _) in layers {
+        expr = match (wrapper, access) {
+            (Wrapper::Mutex, Access::Owned) | (Wrapper::RwLock, Access::Owned) => quote! { #expr.into_inner()#unlock },
+            (Wrapper::RefCell, Access::Owned) => quote! { #expr.into_inner() },
+            (Wrapper::Mutex, _) => quote! { #expr.lock()#unlock },
+            (Wrapper::RwLock, Access::Shared) => quote! { #expr.read()#unlock },
+            (Wrapper::RwLock, Access::Mutable) => quote! { #expr.write()#unlock },
+            (Wrapper::RefCell, Access::Shared) => quote! { #expr.borrow() },
+            (Wrapper::RefCell, Access::Mutable) => quote! { #expr.borrow_mut() },
+            _ => expr,
+        };
+    }
+    expr
+}
+
+/// If the payload type is wrapped in layers that can all be constructed from the value they wrap,
+/// returns the innermost type along with an expression wrapping `value` into the payload type.
+pub fn wrapping_constructor<'a>(
+    ty: &'a syn::Type,
+    value: &syn::Ident,
+    options: &VariantOptions,
+) -> Option<(&'a syn::Type, TokenStream)> {
+    let (layers, inner) = unwrap_payload(ty, options);
+    if layers.is_empty() || !layers.iter().all(|(wrapper, _)| wrapper.is_constructible()) || !is_sized(inner) {
+        return None;
+    }
+    let mut expr = quote! { #value };
+    for (_, layer) in layers.iter().rev() {
+        expr = quote! { <#layer>::new(#expr) };
+    }
+    Some((inner, expr))
+}
+
+/// Returns `false` for types that are known to be unsized, like trait objects, slices, and `str`.
+fn is_sized(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::TraitObject(_) | syn::Type::Slice(_) => false,
+        syn::Type::Path(syn::TypePath { qself: None, path }) => !path.is_ident("str"),
+        _ => true,
+    }
+}
@@ -0,0 +1,74 @@
+//! Supports dispatching trait methods with a `self: Pin<&mut Self>` receiver, like
+//! `Future::poll`, by projecting the pinned enum onto the payload of its active variant.
+//!
+//! The projection treats every dispatched payload as structurally pinned, which is only sound as
+//! long as a pinned payload can never be moved out of a pinned enum. Like `pin-project`, two
+//! guards are generated next to enums with the `pinned` option, which is set automatically when
+//! the enum is linked to a known trait with a pinned receiver:
+//!
+//! - an impl that conflicts with any `Drop` impl for the enum, since `drop` could move a payload
+//!   out through `&mut self`.
+//! - an explicit `Unpin` impl, requiring every dispatched payload to be `Unpin`. This conflicts
+//!   with any handwritten impl that would let the enum be moved after a payload was pinned.
+//!
+//! Payloads stored in a `Box` by `box_above` are projected as the `Box` itself, so they must be
+//! `Unpin` to implement traits like `Future`, just as for a `Box` written in the enum. The guards
+//! always refer to the payload type rather than how it's stored, which is at worst stricter than
+//! needed.
+//!
+//! Traits registered after the enum can't set the option, so linking them to an enum without it
+//! is an error rather than generating the guards once per trait, which would conflict.
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+
+/// Returns `true` if the function argument is a `self: Pin<&mut Self>` receiver.
+pub fn is_pinned_receiver(arg: &syn::FnArg) -> bool {
+    match arg {
+        syn::FnArg::Captured(syn::ArgCaptured {
+            pat: syn::Pat::Ident(syn::PatIdent { ident, .. }),
+            ty: syn::Type::Path(syn::TypePath { qself: None, path }),
+            ..
+        }) => ident == "self" && matches!(path.segments.iter().last(), Some(segment) if segment.ident == "Pin"),
+        _ => false,
+    }
+}
+
+/// Returns `true` if the trait method has a `self: Pin<&mut Self>` receiver.
+pub fn has_pinned_receiver(method: &syn::TraitItemMethod) -> bool {
+    matches!(method.sig.decl.inputs.iter().next(), Some(arg) if is_pinned_receiver(arg))
+}
+
+/// Returns `true` if any method of the trait has a `self: Pin<&mut Self>` receiver.
+pub fn has_pinned_method(traitdef: &syn::ItemTrait) -> bool {
+    traitdef.items.iter().any(|item| matches!(item, syn::TraitItem::Method(method) if has_pinned_receiver(method)))
+}
+
+/// Generates the guards making pin projection sound for an enum with the `pinned` option.
+pub fn generate_pin_guards(enum_def: &EnumDispatchItem) -> TokenStream {
+    let enumname = &enum_def.ident;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let mut unpin_clause = enum_def.generics.clone();
+    let predicates = &mut unpin_clause.make_where_clause().predicates;
+    for variant in enum_def.variants.iter().filter(|variant| variant.dispatches()) {
+        let ty = match &variant.ty {
+            Some(ty) => ty,
+            None => continue,
+        };
+        // Bounds on concrete types are only checked where the impl is used, so that enums with
+        // payloads that aren't `Unpin` still compile.
+        predicates.push(syn::parse_quote! { for<'__enum_dispatch> #ty: ::std::marker::Unpin });
+    }
+    let unpin_clause = &unpin_clause.where_clause;
+    quote! {
+        const _: () = {
+            trait EnumDispatchMustNotImplDrop {}
+            #[allow(drop_bounds)]
+            impl<T: ::std::ops::Drop> EnumDispatchMustNotImplDrop for T {}
+            impl #impl_generics EnumDispatchMustNotImplDrop for #enumname #ty_generics #where_clause {}
+        };
+
+        impl #impl_generics ::std::marker::Unpin for #enumname #ty_generics #unpin_clause {}
+    }
+}
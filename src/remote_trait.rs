@@ -0,0 +1,112 @@
+//! Implements `remote_trait!`, which registers the signature of a trait that can't be annotated
+//! with `#[enum_dispatch]`, such as one from another crate. Enums can then link to the trait by the
+//! path it was declared with, like `#[enum_dispatch(some_crate::Handler)]`.
+//!
+//! Remote traits are cached like local ones, keyed by their path instead of their name. The path
+//! itself is kept with the cached definition as an `#[enum_dispatch_remote(path)]` attribute,
+//! similarly to how enums keep their options, so that generated impls can refer to the trait by it.
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::cache;
+use crate::enum_dispatch_arg_list::EnumDispatchArg;
+
+/// Name of the attribute recording the path of a remote trait in its cached definition.
+const REMOTE_ATTR: &str = "enum_dispatch_remote";
+
+/// A trait declaration like `trait some_crate::Handler { fn handle(&self, req: Request); }`.
+pub struct RemoteTrait {
+    pub path: syn::Path,
+    pub item: syn::ItemTrait,
+}
+
+impl syn::parse::Parse for RemoteTrait {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let unsafety: Option<syn::Token![unsafe]> = input.parse()?;
+        let trait_token: syn::Token![trait] = input.parse()?;
+        let path = input.call(syn::Path::parse_mod_style)?;
+        let ident = &path.segments.iter().last().unwrap().ident;
+        let rest: TokenStream = input.parse()?;
+        let item = syn::parse2(quote! { #(#attrs)* #unsafety #trait_token #ident #rest })?;
+        Ok(RemoteTrait { path, item })
+    }
+}
+
+impl RemoteTrait {
+    /// Returns the trait definition to cache, marked with its path.
+    pub fn to_cached(&self) -> syn::ItemTrait {
+        let path = &self.path;
+        let mut item = self.item.clone();
+        item.attrs.push(syn::parse_quote! { #[enum_dispatch_remote(#path)] });
+        item
+    }
+}
+
+/// Any number of remote trait declarations, as accepted by `remote_trait!`. Each declaration
+/// extends to the end of its braced body.
+pub struct RemoteTraits(pub Vec<RemoteTrait>);
+
+impl syn::parse::Parse for RemoteTraits {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let mut traits = vec![];
+        while !input.is_empty() {
+            let attrs = input.call(syn::Attribute::parse_outer)?;
+            let mut declaration = quote! { #(#attrs)* };
+            while !input.peek(syn::token::Brace) {
+                if input.is_empty() {
+                    return Err(input.error("Expected the body of the remote trait"));
+                }
+                declaration.extend(Some(input.parse::<proc_macro2::TokenTree>()?));
+            }
+            declaration.extend(Some(input.parse::<proc_macro2::TokenTree>()?));
+            traits.push(syn::parse2(declaration)?);
+        }
+        Ok(RemoteTraits(traits))
+    }
+}
+
+/// Removes the marker attribute from a cached trait definition, returning the path of the trait
+/// if it was declared with `remote_trait!`.
+pub fn take_remote_path(traitdef: &mut syn::ItemTrait) -> Option<syn::Path> {
+    let position = traitdef.attrs.iter().position(|attr| attr.path.is_ident(REMOTE_ATTR))?;
+    let attr = traitdef.attrs.remove(position);
+    match attr.tts.into_iter().next() {
+        Some(proc_macro2::TokenTree::Group(group)) => syn::parse2(group.stream()).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the bound that a type implementing a trait linked by an enum's `enum_dispatch` attribute
+/// satisfies, like `some_crate::Handler` or `Foo<T>`. Remote traits are referred to by the path
+/// they were declared with.
+///
+/// Returns `None` if the trait's generic parameters can't all be taken from the enum, or if the
+/// trait isn't registered yet and the enum is generic, since the trait's parameters are unknown.
+pub fn linked_trait_bound(arg: &EnumDispatchArg, enum_generics: &syn::Generics) -> Option<TokenStream> {
+    let written: syn::Path = match arg {
+        EnumDispatchArg::Path(path) => path.clone(),
+        _ => arg.name().clone().into(),
+    };
+    let mut traitdef = match cache::cached_trait(&cache::path_key(&written)) {
+        Some(traitdef) => traitdef,
+        None if enum_generics.params.is_empty() => return Some(quote! { #written }),
+        None => return None,
+    };
+    let path = take_remote_path(&mut traitdef).unwrap_or(written);
+    let enum_params: Vec<String> = enum_generics.params.iter().map(param_name).collect();
+    if !traitdef.generics.params.iter().all(|param| enum_params.contains(&param_name(param))) {
+        return None;
+    }
+    let (_, ty_generics, _) = traitdef.generics.split_for_impl();
+    Some(quote! { #path #ty_generics })
+}
+
+/// Returns the name of a generic parameter, like `T` or `'a`.
+fn param_name(param: &syn::GenericParam) -> String {
+    match param {
+        syn::GenericParam::Type(param) => param.ident.to_string(),
+        syn::GenericParam::Lifetime(param) => param.lifetime.to_string(),
+        syn::GenericParam::Const(param) => param.ident.to_string(),
+    }
+}
@@ -0,0 +1,202 @@
+//! Implements the `serde(...)` enum option, available with the `serde` feature, which implements
+//! `Serialize` and `Deserialize` for the enum using one of serde's other enum representations:
+//!
+//! - `serde(transparent)`: just the payload, like serde's untagged enums. Deserializing tries each
+//!   variant in order.
+//! - `serde(tag = "type")`: the payload's fields along with the variant name, like serde's
+//!   internally tagged enums.
+//! - `serde(tag = "type", content = "data")`: the variant name and the payload side by side, like
+//!   serde's adjacently tagged enums.
+//!
+//! Variant names can be changed using `#[enum_dispatch(rename = "...")]` on the variant.
+//!
+//! Rather than reimplementing these representations, the impls convert to and from hidden mirror
+//! enums that derive serde's traits, so the crate using `enum_dispatch` needs serde's `derive`
+//! feature. The mirror enums only hold the payload of each variant, so variants with other fields
+//! are only deserialized if they can be created using `from_default`.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+use crate::enum_dispatch_arg_list::{EnumDispatchArg, EnumDispatchArgList};
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::expansion::mentions_idents;
+use crate::payload_wrapper::Access;
+use crate::variant_storage::{load_expr, store_stmt};
+
+/// How the enum is represented by its serde impls.
+#[derive(Clone)]
+pub enum SerdeRepr {
+    /// `serde(transparent)`
+    Transparent,
+    /// `serde(tag = "...")`, optionally with `content = "..."`.
+    Tagged {
+        tag: syn::LitStr,
+        content: Option<syn::LitStr>,
+    },
+}
+
+impl SerdeRepr {
+    /// Parses the representation from a `serde(...)` enum option.
+    pub fn from_arg(arg: &EnumDispatchArg) -> syn::parse::Result<Self> {
+        if !cfg!(feature = "serde") {
+            return Err(syn::Error::new(
+                arg.name().span(),
+                "The `serde` enum_dispatch option requires the `serde` feature of enum_dispatch",
+            ));
+        }
+        let list = match arg {
+            EnumDispatchArg::List(_, list) => list,
+            _ => return Err(Self::expected(arg.span())),
+        };
+        let args: EnumDispatchArgList = syn::parse2(list.clone())?;
+        let mut transparent = false;
+        let mut tag = None;
+        let mut content = None;
+        for arg in args.args.iter() {
+            match arg.name().to_string().as_str() {
+                "transparent" => {
+                    arg.expect_flag()?;
+                    transparent = true;
+                }
+                "tag" => tag = Some(arg.parse_value()?),
+                "content" => content = Some(arg.parse_value()?),
+                _ => return Err(arg.unsupported("in the serde enum_dispatch option")),
+            }
+        }
+        match (transparent, tag, content) {
+            (true, None, None) => Ok(SerdeRepr::Transparent),
+            (false, Some(tag), content) => Ok(SerdeRepr::Tagged { tag, content }),
+            _ => Err(Self::expected(arg.span())),
+        }
+    }
+
+    fn expected(span: proc_macro2::Span) -> syn::Error {
+        syn::Error::new(
+            span,
+            "Expected `serde(transparent)`, `serde(tag = \"...\")`, or `serde(tag = \"...\", content = \"...\")`",
+        )
+    }
+
+    /// Returns the container attribute selecting the same representation for a derived impl.
+    pub fn container_attr(&self) -> TokenStream {
+        match self {
+            SerdeRepr::Transparent => quote! { #[serde(untagged)] },
+            SerdeRepr::Tagged { tag, content: None } => quote! { #[serde(tag = #tag)] },
+            SerdeRepr::Tagged { tag, content: Some(content) } => quote! { #[serde(tag = #tag, content = #content)] },
+        }
+    }
+}
+
+/// Converts the representation back into the `serde(...)` enum option.
+impl ToTokens for SerdeRepr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            SerdeRepr::Transparent => quote! { serde(transparent) },
+            SerdeRepr::Tagged { tag, content: None } => quote! { serde(tag = #tag) },
+            SerdeRepr::Tagged { tag, content: Some(content) } => quote! { serde(tag = #tag, content = #content) },
+        });
+    }
+}
+
+/// Generates the `Serialize` and `Deserialize` impls for the enum, along with the mirror enums
+/// they go through. Nothing is generated unless the enum uses the `serde` option.
+pub fn generate_serde_impls(enum_def: &EnumDispatchItem) -> TokenStream {
+    let repr = match &enum_def.options.serde {
+        Some(repr) => repr,
+        None => return TokenStream::new(),
+    };
+    let container_attr = repr.container_attr();
+    let enumname = &enum_def.ident;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let params: Vec<&syn::Ident> = enum_def.generics.type_params().map(|param| &param.ident).collect();
+    let value = syn::Ident::new("value", enum_def.ident.span());
+
+    let mut ser_variants = vec![];
+    let mut ser_arms = vec![];
+    let mut de_variants = vec![];
+    let mut de_arms = vec![];
+    let mut generic_payloads = vec![];
+    for variant in enum_def.variants.iter() {
+        let name = &variant.ident;
+        let rename = variant.options.rename.iter().map(|rename| quote! { #[serde(rename = #rename)] });
+        let rename = quote! { #(#rename)* };
+        match &variant.ty {
+            Some(ty) => {
+                let load = load_expr(enum_def, variant, &value, Access::Shared);
+                let pattern = variant.pattern(enumname, &value);
+                ser_variants.push(quote! { #rename #name(&'__enum_dispatch #ty) });
+                ser_arms.push(quote! { #pattern => __EnumDispatchSerialize::#name(#load) });
+                if let Some(constructor) = variant.constructor(enumname, &value) {
+                    let store = store_stmt(enum_def, variant, &value);
+                    de_variants.push(quote! { #rename #name(#ty) });
+                    de_arms.push(quote! { __EnumDispatchDeserialize::#name(#value) => { #store #constructor } });
+                }
+                if mentions_idents(ty.into_token_stream(), &params) {
+                    generic_payloads.push(ty);
+                }
+            }
+            None => {
+                let pattern = variant.wildcard_pattern(enumname);
+                ser_variants.push(quote! { #rename #name });
+                ser_arms.push(quote! { #pattern => __EnumDispatchSerialize::#name });
+                if let syn::Fields::Unit = variant.fields {
+                    de_variants.push(quote! { #rename #name });
+                    de_arms.push(quote! { __EnumDispatchDeserialize::#name => #enumname::#name });
+                }
+            }
+        }
+    }
+
+    let mut ser_generics = enum_def.generics.clone();
+    ser_generics.params.insert(0, syn::parse_quote! { '__enum_dispatch });
+    let mut ser_impl_generics = enum_def.generics.clone();
+    for ty in generic_payloads.iter() {
+        ser_impl_generics.make_where_clause().predicates.push(syn::parse_quote! { #ty: ::serde::Serialize });
+    }
+    let ser_where_clause = &ser_impl_generics.where_clause;
+    let mut de_generics = enum_def.generics.clone();
+    de_generics.params.insert(0, syn::parse_quote! { '__enum_dispatch_de });
+    for ty in generic_payloads.iter() {
+        de_generics.make_where_clause().predicates.push(syn::parse_quote! { #ty: ::serde::Deserialize<'__enum_dispatch_de> });
+    }
+    let (de_impl_generics, _, de_where_clause) = de_generics.split_for_impl();
+
+    let mut items = quote! {
+        #[derive(::serde::Serialize)]
+        #container_attr
+        enum __EnumDispatchSerialize #ser_generics #where_clause {
+            #(#ser_variants,)*
+        }
+
+        impl #impl_generics ::serde::Serialize for #enumname #ty_generics #ser_where_clause {
+            fn serialize<__S: ::serde::Serializer>(&self, serializer: __S) -> ::std::result::Result<__S::Ok, __S::Error> {
+                let mirror = match self {
+                    #(#ser_arms,)*
+                };
+                ::serde::Serialize::serialize(&mirror, serializer)
+            }
+        }
+    };
+    items.extend(quote! {
+        #[derive(::serde::Deserialize)]
+        #container_attr
+        enum __EnumDispatchDeserialize #impl_generics #where_clause {
+            #(#de_variants,)*
+        }
+
+        impl #de_impl_generics ::serde::Deserialize<'__enum_dispatch_de> for #enumname #ty_generics #de_where_clause {
+            fn deserialize<__D: ::serde::Deserializer<'__enum_dispatch_de>>(deserializer: __D) -> ::std::result::Result<Self, __D::Error> {
+                let mirror: __EnumDispatchDeserialize #ty_generics = ::serde::Deserialize::deserialize(deserializer)?;
+                ::std::result::Result::Ok(match mirror {
+                    #(#de_arms)*
+                })
+            }
+        }
+    });
+    quote! {
+        const _: () = {
+            #items
+        };
+    }
+}
@@ -0,0 +1,145 @@
+//! Implements the `fields(name: Type, ...)` enum option, which generates accessors for fields that
+//! every variant's payload has:
+//!
+//! - `name(&self) -> &Type`
+//! - `name_mut(&mut self) -> &mut Type`, unless the field is declared as `ref name: Type`
+//!
+//! The accessors match on the enum and access the field of the payload directly, so the field
+//! must be visible where the enum is defined. Payloads behind smart pointers are dereferenced by
+//! field access, but locks aren't acquired. Payloads behind shared pointers like `Rc` can't be
+//! borrowed mutably, so enums with such payloads must declare all of their fields with `ref`.
+//! Variants acquiring locks with the `lock` option are rejected, since references into a payload
+//! can't outlive the guard.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::payload_wrapper::{unwrap_payload, Access, Wrapper};
+use crate::variant_storage::load_expr;
+
+/// A field shared by the payloads of every variant, like `name: String`, or `ref name: String`
+/// for a field that only gets the `&self` accessor.
+#[derive(Clone)]
+pub struct SharedField {
+    pub read_only: bool,
+    pub name: syn::Ident,
+    pub ty: syn::Type,
+}
+
+impl syn::parse::Parse for SharedField {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let read_only = input.parse::<Option<syn::Token![ref]>>()?.is_some();
+        let name = input.parse()?;
+        input.parse::<syn::Token![:]>()?;
+        let ty = input.parse()?;
+        Ok(SharedField { read_only, name, ty })
+    }
+}
+
+impl ToTokens for SharedField {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let (name, ty) = (&self.name, &self.ty);
+        if self.read_only {
+            tokens.extend(quote! { ref });
+        }
+        tokens.extend(quote! { #name: #ty });
+    }
+}
+
+/// Parses the list of fields taken by the `fields(...)` enum option.
+pub fn parse_shared_fields(list: &TokenStream) -> syn::parse::Result<Vec<SharedField>> {
+    let parser = syn::punctuated::Punctuated::<SharedField, syn::token::Comma>::parse_terminated;
+    Ok(syn::parse::Parser::parse2(parser, list.clone())?.into_iter().collect())
+}
+
+/// Generates the accessors for each shared field. Nothing is generated unless the enum uses the
+/// `fields` option.
+pub fn generate_field_accessors(enum_def: &EnumDispatchItem) -> TokenStream {
+    if enum_def.options.fields.is_empty() {
+        return TokenStream::new();
+    }
+    if let Some(variant) = enum_def.variants.iter().find(|variant| variant.ty.is_none()) {
+        return syn::Error::new(
+            variant.ident.span(),
+            format!("Variant `{}` has no payload to take the fields of the `fields` enum_dispatch option from", variant.ident),
+        )
+        .to_compile_error();
+    }
+    let locked = enum_def.variants.iter().find(|variant| match &variant.ty {
+        Some(ty) => unwrap_payload(ty, &variant.options).0.iter().any(|(wrapper, _)| wrapper.is_lock()),
+        None => false,
+    });
+    if let Some(variant) = locked {
+        return syn::Error::new(
+            variant.ident.span(),
+            format!(
+                "Variant `{}` holds its payload behind a lock, so the `fields` enum_dispatch option can't return references into it",
+                variant.ident
+            ),
+        )
+        .to_compile_error();
+    }
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let binding = syn::Ident::new("inner", proc_macro2::Span::call_site());
+    let patterns: Vec<syn::Pat> = enum_def.variants.iter().map(|variant| variant.pattern(enumname, &binding)).collect();
+    let shared_payload = enum_def.variants.iter().find(|variant| match &variant.ty {
+        Some(ty) => unwrap_payload(ty, &variant.options)
+            .0
+            .iter()
+            .any(|(wrapper, _)| matches!(wrapper, Wrapper::Rc | Wrapper::Arc | Wrapper::Ref)),
+        None => false,
+    });
+    if let (Some(variant), Some(field)) = (shared_payload, enum_def.options.fields.iter().find(|field| !field.read_only)) {
+        return syn::Error::new(
+            variant.ident.span(),
+            format!(
+                "Variant `{}` holds its payload behind a shared pointer, so `{}_mut` can't be generated. Declare the field as `ref {}: ...` to only generate `{}`",
+                variant.ident, field.name, field.name, field.name
+            ),
+        )
+        .to_compile_error();
+    }
+    let mut methods = TokenStream::new();
+    for field in enum_def.options.fields.iter() {
+        let (name, ty) = (&field.name, &field.ty);
+        let name_mut = syn::Ident::new(&format!("{}_mut", name), name.span());
+        let shared = enum_def.variants.iter().zip(patterns.iter()).map(|(variant, pattern)| {
+            let load = load_expr(enum_def, variant, &binding, Access::Shared);
+            quote! { #pattern => &(#load).#name, }
+        });
+        let mutable = enum_def.variants.iter().zip(patterns.iter()).map(|(variant, pattern)| {
+            let load = load_expr(enum_def, variant, &binding, Access::Mutable);
+            quote! { #pattern => &mut (#load).#name, }
+        });
+        let doc = format!("Returns a reference to the `{}` field of the payload.", name);
+        let mut_doc = format!("Returns a mutable reference to the `{}` field of the payload.", name);
+        methods.extend(quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #name(&self) -> &#ty {
+                match self {
+                    #(#shared)*
+                }
+            }
+        });
+        if field.read_only {
+            continue;
+        }
+        methods.extend(quote! {
+            #[doc = #mut_doc]
+            #[inline]
+            #vis fn #name_mut(&mut self) -> &mut #ty {
+                match self {
+                    #(#mutable)*
+                }
+            }
+        });
+    }
+    quote! {
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            #methods
+        }
+    }
+}
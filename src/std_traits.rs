@@ -0,0 +1,164 @@
+//! A catalog of standard library traits that enums can link to without declaring them with
+//! `remote_trait!`, like `#[enum_dispatch(fmt::Display, error::Error)]`.
+//!
+//! Only the methods that payloads can't be expected to share through the traits' provided methods
+//! are listed, so provided methods such as `Iterator::map` keep their default implementations.
+//! Each trait can be linked by any of its listed names, which all include a module. Bare names
+//! like `Read` are left to traits registered with `#[enum_dispatch]`, which may be declared after
+//! the enum that links them.
+//!
+//! `Future` is always available. `Stream`, `AsyncRead`, and `AsyncWrite` from the `futures` crate
+//! are available with the `stream` and `async-io` features, and refer to the crate as `::futures`.
+use crate::remote_trait::RemoteTrait;
+
+/// Names that each trait can be linked by, along with its declaration.
+const CATALOG: &[(&[&str], &str)] = &[
+    (
+        &["fmt::Display", "std::fmt::Display"],
+        "trait ::std::fmt::Display {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result;
+        }",
+    ),
+    (
+        &["fmt::Debug", "std::fmt::Debug"],
+        "trait ::std::fmt::Debug {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result;
+        }",
+    ),
+    (
+        &["fmt::Write", "std::fmt::Write"],
+        "trait ::std::fmt::Write {
+            fn write_str(&mut self, s: &str) -> ::std::fmt::Result;
+        }",
+    ),
+    (
+        &["error::Error", "std::error::Error"],
+        "trait ::std::error::Error {
+            fn source(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)>;
+        }",
+    ),
+    (
+        &["iter::Iterator", "std::iter::Iterator"],
+        "trait ::std::iter::Iterator {
+            type Item;
+            fn next(&mut self) -> ::std::option::Option<Self::Item>;
+            fn size_hint(&self) -> (usize, ::std::option::Option<usize>);
+        }",
+    ),
+    (
+        &["iter::DoubleEndedIterator", "std::iter::DoubleEndedIterator"],
+        "trait ::std::iter::DoubleEndedIterator: ::std::iter::Iterator {
+            fn next_back(&mut self) -> ::std::option::Option<Self::Item>;
+        }",
+    ),
+    (
+        &["iter::ExactSizeIterator", "std::iter::ExactSizeIterator"],
+        "trait ::std::iter::ExactSizeIterator: ::std::iter::Iterator {
+            fn len(&self) -> usize;
+        }",
+    ),
+    (
+        &["io::Read", "std::io::Read"],
+        "trait ::std::io::Read {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize>;
+        }",
+    ),
+    (
+        &["io::Write", "std::io::Write"],
+        "trait ::std::io::Write {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize>;
+            fn flush(&mut self) -> ::std::io::Result<()>;
+        }",
+    ),
+    (
+        &["io::Seek", "std::io::Seek"],
+        "trait ::std::io::Seek {
+            fn seek(&mut self, pos: ::std::io::SeekFrom) -> ::std::io::Result<u64>;
+        }",
+    ),
+    (
+        &["io::BufRead", "std::io::BufRead"],
+        "trait ::std::io::BufRead {
+            fn fill_buf(&mut self) -> ::std::io::Result<&[u8]>;
+            fn consume(&mut self, amt: usize);
+        }",
+    ),
+    (
+        &["hash::Hash", "std::hash::Hash"],
+        "trait ::std::hash::Hash {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H);
+        }",
+    ),
+    (
+        &["convert::AsRef", "std::convert::AsRef"],
+        "trait ::std::convert::AsRef<T: ?Sized> {
+            fn as_ref(&self) -> &T;
+        }",
+    ),
+    (
+        &["convert::AsMut", "std::convert::AsMut"],
+        "trait ::std::convert::AsMut<T: ?Sized> {
+            fn as_mut(&mut self) -> &mut T;
+        }",
+    ),
+    (
+        &["future::Future", "std::future::Future"],
+        "trait ::std::future::Future {
+            type Output;
+            fn poll(
+                self: ::std::pin::Pin<&mut Self>,
+                cx: &mut ::std::task::Context,
+            ) -> ::std::task::Poll<Self::Output>;
+        }",
+    ),
+    #[cfg(feature = "stream")]
+    (
+        &["stream::Stream", "futures::Stream", "futures::stream::Stream"],
+        "trait ::futures::stream::Stream {
+            type Item;
+            fn poll_next(
+                self: ::std::pin::Pin<&mut Self>,
+                cx: &mut ::std::task::Context,
+            ) -> ::std::task::Poll<::std::option::Option<Self::Item>>;
+            fn size_hint(&self) -> (usize, ::std::option::Option<usize>);
+        }",
+    ),
+    #[cfg(feature = "async-io")]
+    (
+        &["io::AsyncRead", "futures::AsyncRead", "futures::io::AsyncRead"],
+        "trait ::futures::io::AsyncRead {
+            fn poll_read(
+                self: ::std::pin::Pin<&mut Self>,
+                cx: &mut ::std::task::Context,
+                buf: &mut [u8],
+            ) -> ::std::task::Poll<::std::io::Result<usize>>;
+        }",
+    ),
+    #[cfg(feature = "async-io")]
+    (
+        &["io::AsyncWrite", "futures::AsyncWrite", "futures::io::AsyncWrite"],
+        "trait ::futures::io::AsyncWrite {
+            fn poll_write(
+                self: ::std::pin::Pin<&mut Self>,
+                cx: &mut ::std::task::Context,
+                buf: &[u8],
+            ) -> ::std::task::Poll<::std::io::Result<usize>>;
+            fn poll_flush(
+                self: ::std::pin::Pin<&mut Self>,
+                cx: &mut ::std::task::Context,
+            ) -> ::std::task::Poll<::std::io::Result<()>>;
+            fn poll_close(
+                self: ::std::pin::Pin<&mut Self>,
+                cx: &mut ::std::task::Context,
+            ) -> ::std::task::Poll<::std::io::Result<()>>;
+        }",
+    ),
+];
+
+/// Returns the definition of the standard library trait linked by the given name, ready to be
+/// used like a cached remote trait.
+pub fn std_trait(name: &str) -> Option<syn::ItemTrait> {
+    let (_, declaration) = CATALOG.iter().find(|(names, _)| names.contains(&name))?;
+    let remote_trait: RemoteTrait = syn::parse_str(declaration).unwrap();
+    Some(remote_trait.to_cached())
+}
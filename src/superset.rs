@@ -0,0 +1,142 @@
+//! Implements the `superset_of = Subset` enum option, which converts between the enum and a
+//! narrower `enum_dispatch` enum whose variants it also has. Variants are matched by their payload
+//! type, or by name for variants without a payload:
+//!
+//! - `From<Subset>` for the enum always succeeds.
+//! - `TryFrom<Enum>` for the subset returns the original value as the error if its variant has no
+//!   counterpart in the subset.
+//!
+//! The subset must be registered before the enum using it, so that its definition can be checked.
+//! Payloads are moved into the subset using its `From` impls, since its boxed payloads can only be
+//! stored next to its own definition. Payloads that the subset may have boxed are moved out of it
+//! through `Into<Box<T>>`, which works whether or not they were boxed.
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+use crate::cache;
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::enum_dispatch_variant::EnumDispatchVariant;
+use crate::payload_wrapper::Access;
+use crate::variant_storage::{load_expr, storage_selector, store_stmt};
+
+/// Generates the conversions between the enum and its subset. Nothing is generated unless the enum
+/// uses the `superset_of` option.
+pub fn generate_subset_conversions(enum_def: &EnumDispatchItem) -> TokenStream {
+    let subset_path = match &enum_def.options.superset_of {
+        Some(subset_path) => subset_path,
+        None => return TokenStream::new(),
+    };
+    match subset_conversions(enum_def, subset_path) {
+        Ok(conversions) => conversions,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+fn subset_conversions(enum_def: &EnumDispatchItem, subset_path: &syn::Path) -> syn::parse::Result<TokenStream> {
+    let subset_name = &subset_path.segments.iter().last().unwrap().ident;
+    let subset = cache::cached_enum(&subset_name.to_string()).ok_or_else(|| {
+        syn::Error::new(
+            subset_path.span(),
+            format!("`{}` must be registered with `#[enum_dispatch]` before `{}` can be a superset of it", subset_name, enum_def.ident),
+        )
+    })?;
+    if !enum_def.generics.params.is_empty() || !subset.generics.params.is_empty() {
+        return Err(syn::Error::new(subset_path.span(), "The `superset_of` enum_dispatch option doesn't support generic enums"));
+    }
+
+    let enumname = &enum_def.ident;
+    let value = syn::Ident::new("value", proc_macro2::Span::call_site());
+    let mut into_superset = vec![];
+    let mut into_subset = vec![];
+    for subset_variant in subset.variants.iter() {
+        let variant = counterpart(enum_def, subset_variant).ok_or_else(|| {
+            let held = match &subset_variant.ty {
+                Some(ty) => format!("holds `{}`", ty.into_token_stream()),
+                None => "has no payload".to_string(),
+            };
+            syn::Error::new(
+                subset_path.span(),
+                format!(
+                    "Variant `{}` of `{}` {}, but no variant of `{}` matches it",
+                    subset_variant.ident, subset_name, held, enumname
+                ),
+            )
+        })?;
+        let subset_variant_name = &subset_variant.ident;
+        let variant_name = &variant.ident;
+        if subset_variant.ty.is_none() {
+            let subset_pattern = subset_variant.wildcard_pattern(subset_path);
+            into_superset.push(quote! { #subset_pattern => #enumname::#variant_name, });
+            let pattern = variant.wildcard_pattern(enumname);
+            into_subset.push(quote! { #pattern => ::std::result::Result::Ok(#subset_path::#subset_variant_name), });
+            continue;
+        }
+        let constructor = variant.constructor(enumname, &value).ok_or_else(|| {
+            syn::Error::new(
+                subset_path.span(),
+                format!("Variant `{}` of `{}` has other fields, so it can't be created from `{}`", variant_name, enumname, subset_name),
+            )
+        })?;
+        let ty = &subset_variant.ty;
+        let subset_pattern = subset_variant.pattern(subset_path, &value);
+        let unbox = match storage_selector(&subset, subset_variant) {
+            Some(_) => quote! { let #value = *::std::convert::Into::<::std::boxed::Box<#ty>>::into(#value); },
+            None => TokenStream::new(),
+        };
+        let store = store_stmt(enum_def, variant, &value);
+        into_superset.push(quote! {
+            #subset_pattern => {
+                #unbox
+                #store
+                #constructor
+            }
+        });
+        let pattern = variant.pattern(enumname, &value);
+        let load = load_expr(enum_def, variant, &value, Access::Owned);
+        into_subset.push(quote! {
+            #pattern => ::std::result::Result::Ok(<#subset_path as ::std::convert::From<#ty>>::from(#load)),
+        });
+    }
+
+    Ok(quote! {
+        impl ::std::convert::From<#subset_path> for #enumname {
+            fn from(#value: #subset_path) -> Self {
+                match #value {
+                    #(#into_superset)*
+                }
+            }
+        }
+
+        impl ::std::convert::TryFrom<#enumname> for #subset_path {
+            type Error = #enumname;
+
+            #[allow(unreachable_patterns)]
+            fn try_from(#value: #enumname) -> ::std::result::Result<Self, Self::Error> {
+                match #value {
+                    #(#into_subset)*
+                    other => ::std::result::Result::Err(other),
+                }
+            }
+        }
+    })
+}
+
+/// Finds the variant of the enum matching a variant of its subset, which is the one with the same
+/// payload type, or the unit variant with the same name if both are unit variants.
+fn counterpart<'a>(enum_def: &'a EnumDispatchItem, subset_variant: &EnumDispatchVariant) -> Option<&'a EnumDispatchVariant> {
+    match &subset_variant.ty {
+        Some(ty) => {
+            let ty = ty.into_token_stream().to_string();
+            enum_def
+                .variants
+                .iter()
+                .find(|variant| matches!(&variant.ty, Some(other) if other.into_token_stream().to_string() == ty))
+        }
+        None => enum_def
+            .variants
+            .iter()
+            .filter(|_| matches!(subset_variant.fields, syn::Fields::Unit))
+            .find(|variant| matches!(variant.fields, syn::Fields::Unit) && variant.ident == subset_variant.ident),
+    }
+}
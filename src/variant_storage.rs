@@ -0,0 +1,259 @@
+//! Implements the `box_above = N` enum option, which stores the payloads of variants larger than
+//! `N` bytes in a `Box` so that one large variant doesn't inflate the size of the whole enum.
+//!
+//! Sizes aren't known to a procedural macro, so the decision is deferred to the compiler. Each
+//! affected field's type is replaced by a projection like
+//!
+//! ```text
+//! <[(); (::std::mem::size_of::<T>() > N) as usize] as __enum_dispatch_storage_Enum::Storage<T>>::Stored
+//! ```
+//!
+//! which resolves to either `T` or `Box<T>` through the two impls of a helper trait, generated in
+//! a hidden module next to the enum. Since the projection resolves to a concrete type, trait
+//! methods can still be dispatched through it using method call autoderef, without naming the
+//! helper trait. Only conversions into the enum need to go through the helper trait.
+//!
+//! Payload types that refer to the enum's generic parameters can't be sized ahead of time, so they
+//! are always stored inline.
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::enum_dispatch_variant::EnumDispatchVariant;
+use crate::expansion::mentions_idents;
+use crate::payload_wrapper::Access;
+
+/// Returns the name of the hidden module containing the storage selection trait for an enum.
+fn module_name(enum_def: &EnumDispatchItem) -> syn::Ident {
+    syn::Ident::new(&format!("__enum_dispatch_storage_{}", enum_def.ident), enum_def.ident.span())
+}
+
+/// Returns the qualified type selecting the storage of a variant's payload, like
+/// `<[(); 1] as __enum_dispatch_storage_Enum::Storage<T>>`, or `None` if the variant's payload is
+/// always stored inline.
+pub fn storage_selector(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant) -> Option<TokenStream> {
+    let threshold = enum_def.options.box_above.as_ref()?;
+    let ty = variant.ty.as_ref()?;
+    let params: Vec<&syn::Ident> = enum_def
+        .generics
+        .params
+        .iter()
+        .map(|param| match param {
+            syn::GenericParam::Type(param) => &param.ident,
+            syn::GenericParam::Lifetime(param) => &param.lifetime.ident,
+            syn::GenericParam::Const(param) => &param.ident,
+        })
+        .collect();
+    if mentions_idents(quote! { #ty }, &params) {
+        return None;
+    }
+    let module = module_name(enum_def);
+    Some(quote! {
+        <[(); (::std::mem::size_of::<#ty>() > #threshold) as usize] as #module::Storage<#ty>>
+    })
+}
+
+/// Creates an expression for the payload of a variant, given the `binding` of its stored form,
+/// when it isn't known whether `binding` is a shared reference, a mutable reference, or the stored
+/// value itself. The result is the payload accessed in the same way.
+///
+/// Like `load_expr`, the expression refers to the hidden module.
+pub fn load_any_expr(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant, binding: &syn::Ident) -> TokenStream {
+    let selector = match storage_selector(enum_def, variant) {
+        Some(selector) => selector,
+        None => return quote! { #binding },
+    };
+    let module = module_name(enum_def);
+    let ty = &variant.ty;
+    quote! { <#selector::Selected as #module::Load<#ty, _>>::load(#binding) }
+}
+
+/// Returns the type that a variant's payload field is actually declared with, if it differs from
+/// the payload type.
+pub fn stored_type(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant) -> Option<syn::Type> {
+    let selector = storage_selector(enum_def, variant)?;
+    Some(syn::parse_quote! { #selector::Stored })
+}
+
+/// Creates a statement rebinding `value` to its stored form, if the variant's payload may need to
+/// be boxed.
+pub fn store_stmt(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant, value: &syn::Ident) -> TokenStream {
+    match storage_selector(enum_def, variant) {
+        Some(selector) => quote! { let #value = #selector::store(#value); },
+        None => TokenStream::new(),
+    }
+}
+
+/// Creates an expression for the payload of a variant, given the `binding` of its stored form.
+/// `access` describes whether `binding` is a shared reference, a mutable reference, or the stored
+/// value itself. Unlike trait method dispatch, this doesn't rely on autoderef, so the result is
+/// always exactly the payload type (or a reference to it).
+///
+/// The expression refers to the hidden module, so it can only be used next to the enum.
+pub fn load_expr(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant, binding: &syn::Ident, access: Access) -> TokenStream {
+    match storage_selector(enum_def, variant) {
+        Some(selector) => match access {
+            Access::Shared => quote! { #selector::get(#binding) },
+            Access::Mutable => quote! { #selector::get_mut(#binding) },
+            Access::Owned => quote! { #selector::into_inner(#binding) },
+        },
+        None => quote! { #binding },
+    }
+}
+
+/// Generates the hidden module with the storage selection trait, along with the enum's size
+/// report and assertion. Nothing is generated unless the enum uses `box_above` or `max_size`.
+pub fn generate_storage_items(enum_def: &EnumDispatchItem) -> TokenStream {
+    let mut items = TokenStream::new();
+    if enum_def.options.box_above.is_some() {
+        let module = module_name(enum_def);
+        let storage_trait = quote! {
+            /// Selects between inline and boxed storage for a payload of type `T`. Implemented
+            /// for `[(); 0]` (inline) and `[(); 1]` (boxed), which are also named by `Selected`
+            /// so that they can be used with `Load`.
+            pub trait Storage<T> {
+                type Selected;
+                type Stored;
+                fn store(value: T) -> Self::Stored;
+                fn get(stored: &Self::Stored) -> &T;
+                fn get_mut(stored: &mut Self::Stored) -> &mut T;
+                fn into_inner(stored: Self::Stored) -> T;
+            }
+
+            impl<T> Storage<T> for [(); 0] {
+                type Selected = [(); 0];
+                type Stored = T;
+                #[inline]
+                fn store(value: T) -> T {
+                    value
+                }
+                #[inline]
+                fn get(stored: &T) -> &T {
+                    stored
+                }
+                #[inline]
+                fn get_mut(stored: &mut T) -> &mut T {
+                    stored
+                }
+                #[inline]
+                fn into_inner(stored: T) -> T {
+                    stored
+                }
+            }
+
+            impl<T> Storage<T> for [(); 1] {
+                type Selected = [(); 1];
+                type Stored = ::std::boxed::Box<T>;
+                #[inline]
+                fn store(value: T) -> ::std::boxed::Box<T> {
+                    ::std::boxed::Box::new(value)
+                }
+                #[inline]
+                fn get(stored: &::std::boxed::Box<T>) -> &T {
+                    stored
+                }
+                #[inline]
+                fn get_mut(stored: &mut ::std::boxed::Box<T>) -> &mut T {
+                    stored
+                }
+                #[inline]
+                fn into_inner(stored: ::std::boxed::Box<T>) -> T {
+                    *stored
+                }
+            }
+        };
+        let load_trait = quote! {
+            /// Loads the payload of type `T` from its stored form `S`, which may also be
+            /// borrowed, in which case the payload is borrowed in the same way.
+            pub trait Load<T, S> {
+                type Loaded;
+                fn load(stored: S) -> Self::Loaded;
+            }
+
+            impl<T> Load<T, T> for [(); 0] {
+                type Loaded = T;
+                #[inline]
+                fn load(stored: T) -> T {
+                    stored
+                }
+            }
+
+            impl<'a, T> Load<T, &'a T> for [(); 0] {
+                type Loaded = &'a T;
+                #[inline]
+                fn load(stored: &'a T) -> &'a T {
+                    stored
+                }
+            }
+
+            impl<'a, T> Load<T, &'a mut T> for [(); 0] {
+                type Loaded = &'a mut T;
+                #[inline]
+                fn load(stored: &'a mut T) -> &'a mut T {
+                    stored
+                }
+            }
+        };
+        let load_boxed = quote! {
+            impl<T> Load<T, ::std::boxed::Box<T>> for [(); 1] {
+                type Loaded = T;
+                #[inline]
+                fn load(stored: ::std::boxed::Box<T>) -> T {
+                    *stored
+                }
+            }
+
+            impl<'a, T> Load<T, &'a ::std::boxed::Box<T>> for [(); 1] {
+                type Loaded = &'a T;
+                #[inline]
+                fn load(stored: &'a ::std::boxed::Box<T>) -> &'a T {
+                    stored
+                }
+            }
+
+            impl<'a, T> Load<T, &'a mut ::std::boxed::Box<T>> for [(); 1] {
+                type Loaded = &'a mut T;
+                #[inline]
+                fn load(stored: &'a mut ::std::boxed::Box<T>) -> &'a mut T {
+                    stored
+                }
+            }
+        };
+        items.extend(quote! {
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            pub mod #module {
+                #storage_trait
+                #load_trait
+                #load_boxed
+            }
+        });
+    }
+    if enum_def.options.box_above.is_some() || enum_def.options.max_size.is_some() {
+        let enumname = &enum_def.ident;
+        let vis = &enum_def.vis;
+        let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+        items.extend(quote! {
+            impl #impl_generics #enumname #ty_generics #where_clause {
+                /// The size of this enum in bytes, after any oversized variants have been boxed.
+                #vis const ENUM_SIZE: usize = ::std::mem::size_of::<Self>();
+            }
+        });
+    }
+    if let Some(max_size) = &enum_def.options.max_size {
+        let enumname = &enum_def.ident;
+        if enum_def.generics.type_params().next().is_some() || enum_def.generics.const_params().next().is_some() {
+            let error = syn::Error::new(
+                max_size.span(),
+                "enum_dispatch's max_size can't be checked for enums with generic type parameters; check `ENUM_SIZE` of a concrete instantiation instead",
+            );
+            items.extend(error.to_compile_error());
+            return items;
+        }
+        let message = format!("`{}` is larger than its enum_dispatch max_size of {} bytes", enumname, max_size.value());
+        items.extend(quote! {
+            const _: () = assert!(::std::mem::size_of::<#enumname>() <= #max_size, #message);
+        });
+    }
+    items
+}
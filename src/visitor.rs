@@ -0,0 +1,142 @@
+//! Generates visitor traits and a `match` macro for running generic code on whatever an
+//! `enum_dispatch` enum holds. Trait impls can only express code written against a fixed trait,
+//! whereas a visitor's `visit` method is generic, so it can call generic functions with the
+//! payload's concrete type. The `match` macro goes further, since its body is expanded for each
+//! variant, so it can call functions requiring bounds beyond the linked traits, like
+//! `fn process<T: KnobControl + Serialize>(t: &T)`.
+//!
+//! Visitors are given the payload itself where possible. Variants that don't dispatch, or whose
+//! payload is wrapped or another `enum_dispatch` enum, might not have a payload implementing the
+//! linked traits, so the enum itself is given instead, since it implements them all.
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::enum_dispatch_item::EnumDispatchItem;
+use crate::enum_dispatch_variant::{snake_case, EnumDispatchVariant};
+use crate::expansion::nested_enum;
+use crate::payload_wrapper::{unwrap_payload, Access};
+use crate::variant_storage::{load_any_expr, load_expr};
+
+/// The three ways a visitor can receive the payload, along with the suffix of the trait's name.
+const FORMS: [(&str, &str, &str, Access); 3] = [
+    ("", "visit", "accept", Access::Shared),
+    ("Mut", "visit_mut", "accept_mut", Access::Mutable),
+    ("Owned", "visit_owned", "accept_owned", Access::Owned),
+];
+
+/// Checks whether visitors of a variant can be given its payload, rather than the enum itself.
+fn visits_payload(enum_def: &EnumDispatchItem, variant: &EnumDispatchVariant) -> bool {
+    variant.dispatches()
+        && matches!(&variant.ty, Some(ty) if unwrap_payload(ty, &variant.options).0.is_empty())
+        && nested_enum(enum_def, variant).is_none()
+}
+
+/// Generates the visitor traits named by the `visitor` option, like `KnobVisitor`,
+/// `KnobVisitorMut`, and `KnobVisitorOwned` for `visitor = KnobVisitor`, along with the enum's
+/// `accept` methods and the `match` macro. Payloads passed to visitors are bounded by each of the
+/// `trait_bounds`, which come from the traits linked in the enum's own `enum_dispatch` attributes.
+pub fn generate_visitor(enum_def: &EnumDispatchItem, trait_bounds: &[TokenStream]) -> TokenStream {
+    let visitor_name = match &enum_def.options.visitor {
+        Some(visitor_name) => visitor_name,
+        None => return TokenStream::new(),
+    };
+    let enumname = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    let mut visitor_generics = enum_def.generics.clone();
+    visitor_generics.params.push(syn::parse_quote! { __Output });
+    let (visitor_impl_generics, visitor_ty_generics, _) = visitor_generics.split_for_impl();
+    let binding = syn::Ident::new("inner", proc_macro2::Span::call_site());
+
+    let mut items = TokenStream::new();
+    let mut accept_methods = TokenStream::new();
+    for (suffix, visit, accept, access) in FORMS.iter() {
+        let visitor = syn::Ident::new(&format!("{}{}", visitor_name, suffix), visitor_name.span());
+        let visit = syn::Ident::new(visit, enumname.span());
+        let accept = syn::Ident::new(accept, enumname.span());
+        let (receiver, param) = match access {
+            Access::Shared => (quote! { &self }, quote! { &__Variant }),
+            Access::Mutable => (quote! { &mut self }, quote! { &mut __Variant }),
+            Access::Owned => (quote! { self }, quote! { __Variant }),
+        };
+        let visitor_doc = format!(
+            "Runs generic code on the payload of a [`{}`], which is passed {}. See `{}::{}`.",
+            enumname,
+            match access {
+                Access::Shared => "by reference",
+                Access::Mutable => "by mutable reference",
+                Access::Owned => "by value",
+            },
+            enumname,
+            accept,
+        );
+        let arms = enum_def.variants.iter().filter(|variant| visits_payload(enum_def, variant)).map(|variant| {
+            let pattern = variant.pattern(enumname, &binding);
+            let payload = load_expr(enum_def, variant, &binding, *access);
+            quote! { #pattern => visitor.#visit(#payload), }
+        });
+        items.extend(quote! {
+            #[doc = #visitor_doc]
+            #vis trait #visitor #visitor_impl_generics #where_clause {
+                /// Called with the payload of the visited value.
+                fn #visit<__Variant: #(#trait_bounds)+*>(self, variant: #param) -> __Output;
+            }
+        });
+        accept_methods.extend(quote! {
+            /// Passes the payload to the visitor, or the enum itself for variants without a
+            /// payload that implements the linked traits.
+            #[inline]
+            #vis fn #accept<__Visitor: #visitor #visitor_ty_generics, __Output>(#receiver, visitor: __Visitor) -> __Output {
+                match self {
+                    #(#arms)*
+                    other => visitor.#visit(other),
+                }
+            }
+        });
+    }
+    items.extend(quote! {
+        #[allow(dead_code, unreachable_patterns)]
+        impl #impl_generics #enumname #ty_generics #where_clause {
+            #accept_methods
+        }
+    });
+    items.extend(generate_match_macro(enum_def));
+    items
+}
+
+/// Generates a `macro_rules!` macro named after the enum, like `knob_match!` for `Knob`, which
+/// expands its body once per variant with the payload bound to the given name. Unlike a visitor,
+/// the body isn't compiled as a generic function, so it can use anything the payload types have in
+/// common, including inherent methods and fields.
+///
+/// The macro can be used on values, references, and mutable references alike, so boxed payloads
+/// are loaded through the hidden storage module, which means the macro can only be used in the
+/// module defining the enum (or below it, when `box_above` isn't used).
+fn generate_match_macro(enum_def: &EnumDispatchItem) -> TokenStream {
+    let enumname = &enum_def.ident;
+    let macro_name = syn::Ident::new(&format!("{}_match", snake_case(enumname)), enumname.span());
+    let payload = syn::Ident::new("__enum_dispatch_payload", proc_macro2::Span::call_site());
+    let (payload_variants, other_variants): (Vec<&EnumDispatchVariant>, Vec<&EnumDispatchVariant>) =
+        enum_def.variants.iter().partition(|variant| visits_payload(enum_def, variant));
+    let mut arms: Vec<TokenStream> = payload_variants
+        .iter()
+        .map(|variant| {
+            let pattern = variant.pattern(enumname, &payload);
+            let load = load_any_expr(enum_def, variant, &payload);
+            quote! { #pattern => { let $inner = #load; $body } }
+        })
+        .collect();
+    if !other_variants.is_empty() {
+        arms.push(quote! { #payload => { let $inner = #payload; $body } });
+    }
+    quote! {
+        #[allow(unused_macros)]
+        macro_rules! #macro_name {
+            ($value:expr, $inner:ident => $body:expr) => {
+                match $value {
+                    #(#arms)*
+                }
+            };
+        }
+    }
+}
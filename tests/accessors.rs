@@ -0,0 +1,96 @@
+use enum_dispatch::enum_dispatch;
+
+use std::convert::TryFrom;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct HTTPKnob([f64; 32]);
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for f64 {
+    fn get_value(&self) -> f64 {
+        *self
+    }
+}
+
+impl KnobControl for HTTPKnob {
+    fn get_value(&self) -> f64 {
+        self.0.iter().sum()
+    }
+}
+
+#[enum_dispatch(KnobControl, box_above = 64, accessors, try_from)]
+#[derive(Debug, PartialEq)]
+enum AnyKnob {
+    LinearKnob,
+    HTTPKnob,
+    Labeled(&'static str, #[dispatch] f64),
+    #[enum_dispatch(fallback(default_value))]
+    Disconnected,
+}
+
+#[enum_dispatch(accessors, try_from)]
+#[derive(Debug)]
+enum Generic<T> {
+    Inner(T),
+    #[enum_dispatch(no_from)]
+    Linear(LinearKnob),
+}
+
+#[test]
+fn accessors() {
+    let mut knob = AnyKnob::from(LinearKnob { position: 0.5 });
+    assert!(knob.is_linear_knob());
+    assert!(!knob.is_http_knob());
+    assert_eq!(knob.as_linear_knob(), Some(&LinearKnob { position: 0.5 }));
+    assert_eq!(knob.as_http_knob(), None);
+    knob.as_linear_knob_mut().unwrap().position = 0.25;
+    assert_eq!(knob.get_value(), 0.25);
+    assert_eq!(knob.into_linear_knob(), Ok(LinearKnob { position: 0.25 }));
+
+    let knob = AnyKnob::from(HTTPKnob([1.; 32]));
+    assert_eq!(knob.as_http_knob(), Some(&HTTPKnob([1.; 32])));
+    let knob = knob.into_linear_knob().unwrap_err();
+    assert_eq!(knob.into_http_knob(), Ok(HTTPKnob([1.; 32])));
+
+    let knob = AnyKnob::Labeled("volume", 1.);
+    assert!(knob.is_labeled());
+    assert_eq!(knob.as_labeled(), Some(&1.));
+
+    assert!(AnyKnob::Disconnected.is_disconnected());
+    assert!(!AnyKnob::Disconnected.is_labeled());
+
+    let generic = Generic::from(HTTPKnob([0.; 32]));
+    assert!(generic.is_inner());
+    assert_eq!(generic.as_inner().map(KnobControl::get_value), Some(0.));
+    assert!(generic.into_linear().is_err());
+}
+
+#[test]
+fn try_from() {
+    let mut knob = AnyKnob::from(LinearKnob { position: 0.5 });
+    assert_eq!(<&LinearKnob>::try_from(&knob), Ok(&LinearKnob { position: 0.5 }));
+    assert_eq!(<&HTTPKnob>::try_from(&knob), Err(&AnyKnob::LinearKnob(LinearKnob { position: 0.5 })));
+    <&mut LinearKnob>::try_from(&mut knob).unwrap().position = 0.75;
+    assert_eq!(HTTPKnob::try_from(knob), Err(AnyKnob::LinearKnob(LinearKnob { position: 0.75 })));
+
+    let knob = AnyKnob::from(HTTPKnob([2.; 32]));
+    assert_eq!(HTTPKnob::try_from(knob), Ok(HTTPKnob([2.; 32])));
+
+    let generic = Generic::<HTTPKnob>::Linear(LinearKnob { position: 0.5 });
+    assert_eq!(LinearKnob::try_from(generic).unwrap(), LinearKnob { position: 0.5 });
+}
@@ -0,0 +1,95 @@
+use enum_dispatch::enum_dispatch;
+
+use std::any::Any;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MotorizedKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+impl KnobControl for MotorizedKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for [f64; 32] {
+    fn get_value(&self) -> f64 {
+        self.iter().sum()
+    }
+}
+
+#[enum_dispatch(KnobControl, box_above = 64, any_conversions)]
+#[derive(Clone, Debug, PartialEq)]
+enum AnyKnob {
+    LinearKnob,
+    LogarithmicKnob,
+    Bank([f64; 32]),
+}
+
+// `MotorizedKnob` isn't `Clone`, so only `try_from_any` can convert it.
+#[enum_dispatch(KnobControl, any_conversions)]
+#[derive(Debug, PartialEq)]
+enum Motorized {
+    LinearKnob,
+    MotorizedKnob,
+}
+
+#[test]
+fn main() {
+    let legacy: Vec<Box<dyn Any>> = vec![
+        Box::new(LinearKnob { position: 0.5 }),
+        Box::new(LogarithmicKnob { position: 1. }),
+        Box::new([0.25; 32]),
+        Box::new("not a knob"),
+    ];
+    let mut knobs = vec![];
+    let mut rejected = vec![];
+    for value in legacy {
+        match AnyKnob::try_from_any(value) {
+            Ok(knob) => knobs.push(knob),
+            Err(value) => rejected.push(value),
+        }
+    }
+    assert_eq!(knobs.iter().map(KnobControl::get_value).sum::<f64>(), 9.5);
+    assert_eq!(knobs[0], AnyKnob::LinearKnob(LinearKnob { position: 0.5 }));
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].downcast_ref::<&str>(), Some(&"not a knob"));
+
+    let linear = LinearKnob { position: 0.25 };
+    assert_eq!(AnyKnob::try_from_any_ref(&linear), Some(AnyKnob::from(linear.clone())));
+    assert_eq!(AnyKnob::try_from_any_ref(&[1.; 32]).map(|knob| knob.get_value()), Some(32.));
+    assert_eq!(AnyKnob::try_from_any_ref(&1u8), None);
+
+    let motorized = Motorized::try_from_any(Box::new(MotorizedKnob { position: 2. })).unwrap();
+    assert_eq!(motorized, Motorized::MotorizedKnob(MotorizedKnob { position: 2. }));
+    assert_eq!(Motorized::try_from_any_ref(&MotorizedKnob { position: 2. }), None);
+    assert_eq!(Motorized::try_from_any_ref(&linear), Some(Motorized::from(linear)));
+}
@@ -0,0 +1,137 @@
+use enum_dispatch::enum_dispatch;
+
+use std::mem::size_of;
+use std::sync::Mutex;
+
+#[enum_dispatch]
+trait Sample {
+    fn sample(&self) -> u64;
+    fn bump(&mut self);
+    fn into_sample(self) -> u64;
+}
+
+pub struct Small(u64);
+
+pub struct Huge([u64; 64]);
+
+impl Sample for Small {
+    fn sample(&self) -> u64 {
+        self.0
+    }
+
+    fn bump(&mut self) {
+        self.0 += 1;
+    }
+
+    fn into_sample(self) -> u64 {
+        self.0
+    }
+}
+
+impl Sample for Huge {
+    fn sample(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    fn bump(&mut self) {
+        self.0[0] += 1;
+    }
+
+    fn into_sample(self) -> u64 {
+        self.0[63]
+    }
+}
+
+impl Sample for u64 {
+    fn sample(&self) -> u64 {
+        *self
+    }
+
+    fn bump(&mut self) {
+        *self += 1;
+    }
+
+    fn into_sample(self) -> u64 {
+        self
+    }
+}
+
+#[enum_dispatch(Sample, box_above = 32, max_size = 16)]
+enum AnySample {
+    Small,
+    Huge,
+    Counted {
+        count: u8,
+        #[dispatch]
+        huge: Huge,
+    },
+}
+
+#[enum_dispatch(Sample)]
+#[enum_dispatch(box_above = 8)]
+enum Locked {
+    Plain(u64),
+    #[enum_dispatch(lock)]
+    Guarded(Mutex<u64>),
+}
+
+#[enum_dispatch(box_above = 32)]
+enum Registered {
+    Small,
+    Huge,
+}
+
+#[enum_dispatch(Registered)]
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for Small {
+    fn describe(&self) -> String {
+        format!("small {}", self.0)
+    }
+}
+
+impl Describe for Huge {
+    fn describe(&self) -> String {
+        format!("huge {}", self.0.len())
+    }
+}
+
+#[test]
+fn main() {
+    assert!(size_of::<AnySample>() <= 16);
+    assert_eq!(AnySample::ENUM_SIZE, size_of::<AnySample>());
+
+    let mut small = AnySample::from(Small(4));
+    small.bump();
+    assert_eq!(small.sample(), 5);
+    assert!(matches!(small, AnySample::Small(Small(5))));
+    assert_eq!(small.into_sample(), 5);
+
+    let mut huge: AnySample = Huge([1; 64]).into();
+    huge.bump();
+    assert_eq!(huge.sample(), 65);
+    match &huge {
+        AnySample::Huge(boxed) => assert_eq!(Box::as_ref(boxed).0[0], 2),
+        _ => unreachable!(),
+    }
+    assert_eq!(huge.into_sample(), 1);
+
+    let counted = AnySample::Counted { count: 1, huge: Box::new(Huge([2; 64])) };
+    assert_eq!(counted.sample(), 128);
+    assert!(matches!(counted, AnySample::Counted { count: 1, .. }));
+
+    assert_eq!(Registered::ENUM_SIZE, size_of::<Box<Huge>>() + 8);
+    assert_eq!(Registered::from(Huge([0; 64])).describe(), "huge 64");
+    assert_eq!(Registered::from(Small(2)).describe(), "small 2");
+
+    let mut plain = Locked::from(3u64);
+    plain.bump();
+    assert_eq!(plain.sample(), 4);
+    let mut guarded = Locked::from(Mutex::new(7u64));
+    guarded.bump();
+    assert_eq!(guarded.sample(), 8);
+    assert!(matches!(guarded, Locked::Guarded(ref boxed) if *boxed.lock().unwrap() == 8));
+    assert_eq!(guarded.into_sample(), 8);
+}
@@ -126,10 +126,10 @@ fn main() {
     }.into();
 
     match d {
-        Traited::A(_) => assert!(false),
-        Traited::B(_) => assert!(false),
-        Traited::C(_) => assert!(false),
-        Traited::LetterD(_) => assert!(true),
+        Traited::A(_) => unreachable!(),
+        Traited::B(_) => unreachable!(),
+        Traited::C(_) => unreachable!(),
+        Traited::LetterD(_) => (),
     }
 
     assert_eq!(a.describe(), 'A');
@@ -0,0 +1,144 @@
+use std::convert::TryFrom;
+
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn set_position(&mut self, value: f64);
+    fn get_value(&self) -> f64;
+}
+
+// Not object safe, so no trait object conversions are generated for it.
+#[enum_dispatch]
+trait Scaled {
+    fn midpoint<T: Into<f64>>(&self, other: T) -> f64;
+}
+
+#[derive(Debug, Default)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Slider {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+impl KnobControl for Slider {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position * 10.
+    }
+}
+
+impl Scaled for Slider {
+    fn midpoint<T: Into<f64>>(&self, other: T) -> f64 {
+        (self.position + other.into()) / 2.
+    }
+}
+
+#[enum_dispatch(KnobControl, box_above = 64, dyn_conversions)]
+#[derive(Debug)]
+enum AnyKnob {
+    LinearKnob,
+    LogarithmicKnob,
+    Bank([LinearKnob; 16]),
+    Labeled(&'static str, #[dispatch] Slider),
+}
+
+impl KnobControl for [LinearKnob; 16] {
+    fn set_position(&mut self, value: f64) {
+        self.iter_mut().for_each(|knob| knob.set_position(value));
+    }
+
+    fn get_value(&self) -> f64 {
+        self.iter().map(KnobControl::get_value).sum()
+    }
+}
+
+#[enum_dispatch(Scaled, dyn_conversions)]
+enum ScaledKnob {
+    Slider,
+}
+
+impl KnobControl for Box<dyn KnobControl> {
+    fn set_position(&mut self, value: f64) {
+        (**self).set_position(value);
+    }
+
+    fn get_value(&self) -> f64 {
+        (**self).get_value()
+    }
+}
+
+// The enum converts into `Box<dyn KnobControl>` infallibly, so `try_from` skips that payload.
+#[enum_dispatch(KnobControl, dyn_conversions, try_from)]
+enum OpenKnob {
+    Slider,
+    Boxed(Box<dyn KnobControl>),
+}
+
+fn total(knobs: &[&dyn KnobControl]) -> f64 {
+    knobs.iter().map(|knob| knob.get_value()).sum()
+}
+
+fn reset(knob: &mut dyn KnobControl) {
+    knob.set_position(0.);
+}
+
+#[test]
+fn main() {
+    let linear = AnyKnob::from(LinearKnob { position: 0.5 });
+    let logarithmic = AnyKnob::from(LogarithmicKnob { position: 1. });
+    assert_eq!(total(&[linear.as_dyn(), logarithmic.as_dyn()]), 1.5);
+
+    let mut labeled = AnyKnob::Labeled("volume", Slider { position: 0.5 });
+    assert_eq!(labeled.as_dyn::<dyn KnobControl>().get_value(), 5.);
+    reset(labeled.as_dyn_mut());
+    assert_eq!(labeled.get_value(), 0.);
+    assert!(matches!(labeled, AnyKnob::Labeled("volume", _)));
+
+    let mut bank = AnyKnob::from(<[LinearKnob; 16]>::default());
+    bank.as_dyn_mut::<dyn KnobControl>().set_position(0.5);
+    assert_eq!(bank.get_value(), 8.);
+
+    let boxed: Vec<Box<dyn KnobControl>> = vec![linear.into(), logarithmic.into_box_dyn(), bank.into()];
+    assert_eq!(boxed.iter().map(|knob| knob.get_value()).sum::<f64>(), 9.5);
+
+    let slider = ScaledKnob::from(Slider { position: 1. });
+    assert_eq!(slider.midpoint(2u8), 1.5);
+
+    let open = OpenKnob::from(Box::new(Slider { position: 0.5 }) as Box<dyn KnobControl>);
+    assert_eq!(open.as_dyn::<dyn KnobControl>().get_value(), 5.);
+    let boxed: Box<dyn KnobControl> = open.into();
+    assert_eq!(boxed.get_value(), 5.);
+    assert!(Slider::try_from(OpenKnob::from(Slider { position: 1. })).is_ok());
+}
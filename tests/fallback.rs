@@ -0,0 +1,114 @@
+use enum_dispatch::{enum_dispatch, enum_dispatch_enum};
+
+#[enum_dispatch]
+trait Sink {
+    fn write(&mut self, data: &str) -> usize {
+        data.len()
+    }
+    fn flush(&mut self) -> bool;
+}
+
+#[derive(Default)]
+pub struct FileSink {
+    written: Vec<String>,
+}
+
+impl Sink for FileSink {
+    fn write(&mut self, data: &str) -> usize {
+        self.written.push(data.to_string());
+        self.written.len()
+    }
+
+    fn flush(&mut self) -> bool {
+        self.written.clear();
+        true
+    }
+}
+
+#[enum_dispatch(Sink)]
+enum AnySink {
+    FileSink,
+    #[enum_dispatch(fallback(default_value))]
+    Null,
+    #[enum_dispatch(fallback(default_value))]
+    Unsupported(String),
+}
+
+#[enum_dispatch]
+trait Named {
+    fn name(&self, prefix: &str) -> String;
+}
+
+impl Named for FileSink {
+    fn name(&self, prefix: &str) -> String {
+        format!("{}file", prefix)
+    }
+}
+
+fn anonymous(_: &Labeled, prefix: &str) -> String {
+    format!("{}anonymous", prefix)
+}
+
+enum_dispatch_enum! {
+    #[enum_dispatch(Named)]
+    enum Labeled {
+        FileSink,
+        #[enum_dispatch(fallback = anonymous)]
+        Anonymous,
+    }
+}
+
+#[enum_dispatch]
+trait Counted {
+    fn count(&self) -> usize {
+        42
+    }
+}
+
+impl Counted for FileSink {
+    fn count(&self) -> usize {
+        self.written.len()
+    }
+}
+
+#[enum_dispatch(Counted)]
+enum CountedSink {
+    FileSink,
+    #[enum_dispatch(fallback(default_method))]
+    Constant,
+}
+
+#[test]
+fn default_value() {
+    let mut file = AnySink::from(FileSink::default());
+    let mut null = AnySink::Null;
+    let mut unsupported = AnySink::from("ftp".to_string());
+
+    assert_eq!(file.write("a"), 1);
+    assert_eq!(file.write("b"), 2);
+    assert!(file.flush());
+    assert_eq!(null.write("abc"), 0);
+    assert!(!null.flush());
+    assert_eq!(unsupported.write("abc"), 0);
+    assert!(!unsupported.flush());
+    match unsupported {
+        AnySink::Unsupported(scheme) => assert_eq!(scheme, "ftp"),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn function() {
+    let file = Labeled::from(FileSink::default());
+    let anonymous = Labeled::Anonymous;
+    assert_eq!(file.name("a "), "a file");
+    assert_eq!(anonymous.name("an "), "an anonymous");
+}
+
+#[test]
+fn default_method() {
+    let file = CountedSink::from(FileSink::default());
+    let constant = CountedSink::Constant;
+    assert_eq!(file.count(), 0);
+    assert_eq!(constant.count(), 42);
+}
@@ -0,0 +1,108 @@
+use enum_dispatch::enum_dispatch;
+
+use std::collections::HashMap;
+
+#[enum_dispatch]
+pub trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Default)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+#[derive(Default)]
+pub struct SteppedKnob {
+    steps: u32,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        self.position.log2()
+    }
+}
+
+impl KnobControl for SteppedKnob {
+    fn get_value(&self) -> f64 {
+        self.steps as f64
+    }
+}
+
+/// Settings read from a configuration file.
+pub type Settings = HashMap<String, f64>;
+
+fn logarithmic(settings: &Settings) -> LogarithmicKnob {
+    LogarithmicKnob { position: settings["position"] }
+}
+
+fn stepped(settings: &Settings) -> SteppedKnob {
+    SteppedKnob { steps: settings["steps"] as u32 }
+}
+
+#[enum_dispatch(KnobControl, from_name)]
+pub enum Knob {
+    LinearKnob,
+    LogarithmicKnob,
+    #[enum_dispatch(fallback(default_value))]
+    Disabled,
+}
+
+#[enum_dispatch(KnobControl, from_name(args = &Settings), box_above = 16)]
+pub enum ConfiguredKnob {
+    LinearKnob,
+    #[enum_dispatch(constructor = logarithmic)]
+    LogarithmicKnob,
+    #[enum_dispatch(constructor = stepped)]
+    Stepped(SteppedKnob),
+}
+
+#[enum_dispatch(KnobControl, from_name, no_from)]
+pub enum GenericKnob<K: KnobControl> {
+    Custom(K),
+    LinearKnob,
+}
+
+#[test]
+fn registered_names() {
+    assert_eq!(Knob::registered_names(), &["LinearKnob", "LogarithmicKnob", "Disabled"]);
+    assert_eq!(ConfiguredKnob::registered_names(), &["LinearKnob", "LogarithmicKnob", "Stepped"]);
+}
+
+#[test]
+fn from_name_uses_defaults() {
+    assert_eq!(Knob::from_name("LinearKnob").map(|knob| knob.get_value()), Some(0.0));
+    assert!(matches!(Knob::from_name("Disabled"), Some(Knob::Disabled)));
+    // `LogarithmicKnob` doesn't implement `Default`.
+    assert!(Knob::from_name("LogarithmicKnob").is_none());
+    assert!(Knob::from_name("linear").is_none());
+
+    assert!(GenericKnob::<SteppedKnob>::from_name("LinearKnob").is_some());
+    assert!(GenericKnob::<SteppedKnob>::from_name("Custom").is_none());
+}
+
+#[test]
+fn from_name_with_constructors() {
+    let settings: Settings = [("position".to_string(), 8.0), ("steps".to_string(), 4.0)].iter().cloned().collect();
+
+    let values: Vec<Option<f64>> = ConfiguredKnob::registered_names()
+        .iter()
+        .map(|name| ConfiguredKnob::from_name_with(name, &settings).map(|knob| knob.get_value()))
+        .collect();
+    assert_eq!(values, vec![Some(0.0), Some(3.0), Some(4.0)]);
+    assert!(ConfiguredKnob::from_name_with("Unknown", &settings).is_none());
+
+    // Constructors are only used by `from_name_with`.
+    assert!(ConfiguredKnob::from_name("LogarithmicKnob").is_none());
+    assert_eq!(ConfiguredKnob::from_name("Stepped").map(|knob| knob.get_value()), Some(0.0));
+}
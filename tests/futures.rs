@@ -0,0 +1,201 @@
+// Pin projection is generated with `unsafe` code, which is allowed where it's used.
+#![deny(unsafe_code)]
+
+use enum_dispatch::enum_dispatch;
+
+use futures::executor::block_on;
+
+use std::future::{ready, Future, Ready};
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Returns `Pending` until it has been polled `remaining` more times.
+pub struct Countdown {
+    remaining: u32,
+}
+
+impl Future for Countdown {
+    type Output = u32;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<u32> {
+        if self.remaining == 0 {
+            return Poll::Ready(3);
+        }
+        self.remaining -= 1;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// A future that can't be moved once it's been pinned.
+pub struct Pinned {
+    value: u32,
+    _pin: PhantomPinned,
+}
+
+impl Future for Pinned {
+    type Output = u32;
+
+    fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<u32> {
+        Poll::Ready(self.value)
+    }
+}
+
+#[enum_dispatch(future::Future)]
+pub enum Job {
+    Ready(Ready<u32>),
+    Countdown,
+    Boxed(Pin<Box<dyn Future<Output = u32>>>),
+}
+
+#[enum_dispatch(future::Future)]
+pub enum PinnedJob {
+    Pinned,
+    Countdown,
+    #[enum_dispatch(fallback = cancelled)]
+    Cancelled,
+}
+
+fn cancelled(_: Pin<&mut PinnedJob>, _: &mut Context) -> Poll<u32> {
+    Poll::Ready(0)
+}
+
+#[enum_dispatch(future::Future)]
+pub enum Nested {
+    Job,
+    Pinned,
+}
+
+// `Tick` is declared after the enum, so the guards have to be requested with `pinned`.
+#[enum_dispatch(Tick, pinned)]
+pub enum Ticker {
+    Countdown,
+}
+
+#[enum_dispatch]
+pub trait Tick {
+    fn tick(self: Pin<&mut Self>) -> u32;
+}
+
+impl Tick for Countdown {
+    fn tick(mut self: Pin<&mut Self>) -> u32 {
+        self.remaining -= 1;
+        self.remaining
+    }
+}
+
+#[test]
+fn unpin_payloads() {
+    let jobs: Vec<Job> = vec![ready(1).into(), Countdown { remaining: 2 }.into(), Job::Boxed(Box::pin(async { 7 }))];
+    let outputs: Vec<u32> = jobs.into_iter().map(block_on).collect();
+    assert_eq!(outputs, vec![1, 3, 7]);
+
+    let mut job: Job = Countdown { remaining: 1 }.into();
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(Pin::new(&mut job).poll(&mut cx), Poll::Pending);
+    assert_eq!(Pin::new(&mut job).poll(&mut cx), Poll::Ready(3));
+}
+
+#[test]
+fn pinned_payloads() {
+    let pinned: PinnedJob = Pinned { value: 5, _pin: PhantomPinned }.into();
+    assert_eq!(block_on(Box::pin(pinned)), 5);
+    assert_eq!(block_on(PinnedJob::from(Countdown { remaining: 1 })), 3);
+    assert_eq!(block_on(PinnedJob::Cancelled), 0);
+}
+
+#[test]
+fn nested_futures() {
+    assert_eq!(block_on(Nested::from(Job::from(ready(9)))), 9);
+    assert_eq!(block_on(Box::pin(Nested::from(Pinned { value: 4, _pin: PhantomPinned }))), 4);
+}
+
+#[test]
+fn traits_declared_later() {
+    let mut ticker = Ticker::from(Countdown { remaining: 3 });
+    assert_eq!(Pin::new(&mut ticker).tick(), 2);
+    assert_eq!(Pin::new(&mut ticker).tick(), 1);
+}
+
+#[test]
+fn async_fn_awaits_enum() {
+    async fn total(jobs: Vec<Job>) -> u32 {
+        let mut total = 0;
+        for job in jobs {
+            total += job.await;
+        }
+        total
+    }
+
+    assert_eq!(block_on(total(vec![ready(1).into(), Countdown { remaining: 3 }.into()])), 4);
+}
+
+#[cfg(feature = "stream")]
+mod stream {
+    use super::*;
+
+    use futures::stream::{self, Iter, Stream, StreamExt};
+
+    #[enum_dispatch(stream::Stream)]
+    pub enum Numbers {
+        Iter(Iter<std::ops::Range<u32>>),
+        Repeat(stream::Take<stream::Repeat<u32>>),
+    }
+
+    #[test]
+    fn streams_dispatch() {
+        let numbers: Numbers = stream::iter(0..3).into();
+        assert_eq!(numbers.size_hint(), (3, Some(3)));
+        assert_eq!(block_on(numbers.collect::<Vec<_>>()), vec![0, 1, 2]);
+
+        let repeated: Numbers = stream::repeat(8).take(2).into();
+        assert_eq!(block_on(repeated.collect::<Vec<_>>()), vec![8, 8]);
+    }
+}
+
+#[cfg(feature = "async-io")]
+mod async_io {
+    use super::*;
+
+    use futures::io::{AsyncReadExt, AsyncWriteExt, Cursor, Empty, Sink};
+
+    #[enum_dispatch(futures::io::AsyncRead)]
+    pub enum Source {
+        Cursor(Cursor<Vec<u8>>),
+        Empty,
+    }
+
+    #[enum_dispatch(futures::io::AsyncWrite)]
+    pub enum Destination {
+        Cursor(Cursor<Vec<u8>>),
+        Sink,
+    }
+
+    #[test]
+    fn readers_dispatch() {
+        let mut source: Source = Cursor::new(b"pinned".to_vec()).into();
+        let mut text = String::new();
+        block_on(source.read_to_string(&mut text)).unwrap();
+        assert_eq!(text, "pinned");
+
+        let mut empty: Source = futures::io::empty().into();
+        assert_eq!(block_on(empty.read(&mut [0; 4])).unwrap(), 0);
+    }
+
+    #[test]
+    fn writers_dispatch() {
+        let mut destination: Destination = Cursor::new(vec![]).into();
+        block_on(destination.write_all(b"abc")).unwrap();
+        block_on(destination.close()).unwrap();
+        match destination {
+            Destination::Cursor(cursor) => assert_eq!(cursor.into_inner(), b"abc"),
+            Destination::Sink(_) => unreachable!(),
+        }
+
+        let mut sink: Destination = futures::io::sink().into();
+        assert_eq!(block_on(sink.write(b"ignored")).unwrap(), 7);
+        block_on(sink.flush()).unwrap();
+    }
+}
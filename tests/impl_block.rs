@@ -0,0 +1,138 @@
+use enum_dispatch::enum_dispatch;
+
+pub struct LinearKnob {
+    id: u64,
+    position: f64,
+}
+
+pub struct LogarithmicKnob {
+    id: u64,
+    position: f64,
+    history: [f64; 8],
+}
+
+impl LinearKnob {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn reset(&mut self) {
+        self.position = 0.0;
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+}
+
+impl LogarithmicKnob {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn reset(&mut self) {
+        self.position = 1.0;
+        self.history = [0.0; 8];
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position.log2()
+    }
+}
+
+#[enum_dispatch]
+pub enum Knob {
+    LinearKnob,
+    LogarithmicKnob,
+    #[enum_dispatch(fallback(default_value))]
+    Disabled,
+}
+
+#[enum_dispatch(Knob)]
+impl Knob {
+    /// Returns the knob's unique id.
+    pub fn id(&self) -> u64;
+    pub fn reset(&mut self);
+}
+
+mod late {
+    use super::{LinearKnob, LogarithmicKnob};
+    use enum_dispatch::enum_dispatch;
+
+    // The enum is registered after the impl block, so the impl is only generated then.
+    #[enum_dispatch(BoxedKnob)]
+    impl BoxedKnob {
+        pub fn position(&self) -> f64;
+        pub(crate) fn id(&self) -> u64;
+    }
+
+    #[enum_dispatch(box_above = 32)]
+    pub enum BoxedKnob {
+        LinearKnob,
+        LogarithmicKnob,
+    }
+}
+
+pub struct Gain<T> {
+    level: T,
+}
+
+pub struct Offset<T> {
+    level: T,
+}
+
+impl<T: Copy> Gain<T> {
+    pub fn level(&self) -> T {
+        self.level
+    }
+}
+
+impl<T: Copy> Offset<T> {
+    pub fn level(&self) -> T {
+        self.level
+    }
+}
+
+#[enum_dispatch]
+pub enum Stage<T> {
+    Gain(Gain<T>),
+    Offset(Offset<T>),
+}
+
+// The block's own generics and bounds are used for the generated impl.
+#[enum_dispatch(Stage)]
+impl<T> Stage<T>
+where
+    T: Copy,
+{
+    pub fn level(&self) -> T;
+}
+
+#[test]
+fn dispatches_to_inherent_methods() {
+    let mut knobs = [
+        Knob::from(LinearKnob { id: 1, position: 0.5 }),
+        Knob::from(LogarithmicKnob { id: 2, position: 4.0, history: [1.0; 8] }),
+        Knob::Disabled,
+    ];
+    assert_eq!(knobs.iter().map(Knob::id).collect::<Vec<_>>(), vec![1, 2, 0]);
+    knobs.iter_mut().for_each(Knob::reset);
+    match &knobs[1] {
+        Knob::LogarithmicKnob(knob) => assert_eq!(knob.history, [0.0; 8]),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn impl_block_before_enum() {
+    let knob = late::BoxedKnob::from(LogarithmicKnob { id: 3, position: 8.0, history: [0.0; 8] });
+    assert_eq!(knob.position(), 3.0);
+    assert_eq!(knob.id(), 3);
+    assert_eq!(late::BoxedKnob::from(LinearKnob { id: 4, position: 0.25 }).position(), 0.25);
+}
+
+#[test]
+fn generic_impl_block() {
+    assert_eq!(Stage::from(Gain { level: 2u8 }).level(), 2);
+    assert_eq!(Stage::from(Offset { level: -1.5 }).level(), -1.5);
+}
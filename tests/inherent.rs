@@ -0,0 +1,126 @@
+mod controls {
+    use enum_dispatch::enum_dispatch;
+
+    #[enum_dispatch]
+    pub trait KnobControl {
+        /// Returns the knob's current value.
+        fn get_value(&self) -> f64;
+        fn set_position(&mut self, position: f64);
+        fn into_position(self) -> f64;
+        fn describe(&self) -> String {
+            format!("knob at {}", self.get_value())
+        }
+    }
+
+    #[enum_dispatch]
+    pub trait Named {
+        fn name(&self) -> &'static str;
+    }
+}
+
+mod knobs {
+    use crate::controls::{KnobControl, Named};
+    use enum_dispatch::enum_dispatch;
+
+    pub struct LinearKnob {
+        pub position: f64,
+    }
+
+    pub struct LogarithmicKnob {
+        pub position: f64,
+    }
+
+    impl KnobControl for LinearKnob {
+        fn get_value(&self) -> f64 {
+            self.position
+        }
+
+        fn set_position(&mut self, position: f64) {
+            self.position = position;
+        }
+
+        fn into_position(self) -> f64 {
+            self.position
+        }
+    }
+
+    impl KnobControl for LogarithmicKnob {
+        fn get_value(&self) -> f64 {
+            self.position.log2()
+        }
+
+        fn set_position(&mut self, position: f64) {
+            self.position = position;
+        }
+
+        fn into_position(self) -> f64 {
+            self.position
+        }
+    }
+
+    impl Named for LinearKnob {
+        fn name(&self) -> &'static str {
+            "linear"
+        }
+    }
+
+    impl Named for LogarithmicKnob {
+        fn name(&self) -> &'static str {
+            "logarithmic"
+        }
+    }
+
+    #[enum_dispatch(KnobControl, inherent)]
+    #[enum_dispatch(Named)]
+    pub enum Knob {
+        LinearKnob,
+        LogarithmicKnob,
+    }
+
+    pub struct Wrapper<T>(pub T);
+
+    impl<T: KnobControl> KnobControl for Wrapper<T> {
+        fn get_value(&self) -> f64 {
+            self.0.get_value()
+        }
+
+        fn set_position(&mut self, position: f64) {
+            self.0.set_position(position)
+        }
+
+        fn into_position(self) -> f64 {
+            self.0.into_position()
+        }
+    }
+
+    #[enum_dispatch(KnobControl, inherent, no_from)]
+    pub enum GenericKnob<T: KnobControl> {
+        Wrapped(Wrapper<T>),
+        LinearKnob,
+    }
+}
+
+// Neither trait is imported here.
+use knobs::{GenericKnob, Knob, LinearKnob, LogarithmicKnob, Wrapper};
+
+#[test]
+fn calls_without_importing_the_trait() {
+    let mut knob = Knob::from(LinearKnob { position: 0.5 });
+    assert_eq!(knob.get_value(), 0.5);
+    knob.set_position(8.0);
+    assert_eq!(knob.describe(), "knob at 8");
+    assert_eq!(knob.into_position(), 8.0);
+    assert_eq!(Knob::from(LogarithmicKnob { position: 8.0 }).get_value(), 3.0);
+}
+
+#[test]
+fn only_traits_linked_with_inherent() {
+    use controls::Named;
+    assert_eq!(Knob::from(LinearKnob { position: 0.0 }).name(), "linear");
+}
+
+#[test]
+fn generic_enums() {
+    let knob: GenericKnob<LogarithmicKnob> = GenericKnob::Wrapped(Wrapper(LogarithmicKnob { position: 4.0 }));
+    assert_eq!(knob.get_value(), 2.0);
+}
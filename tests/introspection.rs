@@ -0,0 +1,63 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait Shape {
+    fn area(&self) -> f32;
+}
+
+pub struct Square(f32);
+
+pub mod circles {
+    pub struct Circle(pub f32);
+}
+
+impl Shape for Square {
+    fn area(&self) -> f32 {
+        self.0 * self.0
+    }
+}
+
+impl Shape for circles::Circle {
+    fn area(&self) -> f32 {
+        self.0 * self.0 * 3.
+    }
+}
+
+use circles::Circle;
+
+#[enum_dispatch(Shape, introspection)]
+enum AnyShape {
+    Square,
+    Round {
+        #[dispatch]
+        circle: Circle,
+        label: &'static str,
+    },
+    #[enum_dispatch(fallback(default_value))]
+    Empty,
+}
+
+#[test]
+fn main() {
+    assert_eq!(AnyShape::VARIANT_COUNT, 3);
+    assert_eq!(AnyShape::VARIANT_NAMES, &["Square", "Round", "Empty"]);
+
+    let shapes = [
+        AnyShape::from(Square(1.)),
+        AnyShape::Round { circle: Circle(1.), label: "unit" },
+        AnyShape::Empty,
+    ];
+    let names: Vec<&str> = shapes.iter().map(AnyShape::variant_name).collect();
+    assert_eq!(names, AnyShape::VARIANT_NAMES);
+    let indices: Vec<usize> = shapes.iter().map(AnyShape::variant_index).collect();
+    assert_eq!(indices, vec![0, 1, 2]);
+    for shape in shapes.iter() {
+        assert_eq!(AnyShape::VARIANT_NAMES[shape.variant_index()], shape.variant_name());
+    }
+
+    assert!(shapes[0].variant_type_name().ends_with("Square"));
+    assert!(shapes[1].variant_type_name().ends_with("circles::Circle"));
+    assert!(shapes[2].variant_type_name().ends_with("AnyShape"));
+    assert!(matches!(shapes[1], AnyShape::Round { label: "unit", .. }));
+    assert_eq!(shapes.iter().map(Shape::area).sum::<f32>(), 4.);
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "schemars")]
+
+use enum_dispatch::enum_dispatch;
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct LogarithmicKnob {
+    position: f64,
+    base: u32,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        self.position.log(self.base as f64)
+    }
+}
+
+#[enum_dispatch(KnobControl, json_schema)]
+#[derive(Serialize, Deserialize)]
+pub enum Knob {
+    LinearKnob,
+    LogarithmicKnob,
+}
+
+#[enum_dispatch(json_schema, no_from)]
+pub enum Generic<K> {
+    Knob(K),
+    LinearKnob,
+}
+
+/// Returns the schemas of the alternatives of the top-level `oneOf`.
+fn alternatives(schema: &Value) -> &Vec<Value> {
+    schema["oneOf"].as_array().expect("expected a `oneOf` schema")
+}
+
+#[test]
+fn externally_tagged_by_default() {
+    let schema = schema_for!(Knob).to_value();
+    assert_eq!(schema["title"], "Knob");
+    let alternatives = alternatives(&schema);
+    assert_eq!(alternatives.len(), 2);
+    assert_eq!(alternatives[0]["required"], json!(["LinearKnob"]));
+    assert_eq!(alternatives[1]["properties"]["LogarithmicKnob"]["$ref"], "#/$defs/LogarithmicKnob");
+    assert!(schema["$defs"]["LogarithmicKnob"]["properties"]["base"].is_object());
+}
+
+#[test]
+fn generic_enums() {
+    let schema = schema_for!(Generic<LogarithmicKnob>).to_value();
+    assert_eq!(schema["title"], "Generic_for_LogarithmicKnob");
+    assert_eq!(alternatives(&schema).len(), 2);
+}
+
+#[cfg(feature = "serde")]
+mod tagged {
+    use super::*;
+
+    #[enum_dispatch(KnobControl, serde(tag = "type"), json_schema)]
+    pub enum Tagged {
+        LinearKnob,
+        #[enum_dispatch(rename = "log")]
+        LogarithmicKnob,
+        #[enum_dispatch(fallback(default_value))]
+        Disabled,
+    }
+
+    #[enum_dispatch(KnobControl, serde(tag = "type", content = "data"), json_schema)]
+    pub enum Adjacent {
+        LinearKnob,
+        LogarithmicKnob,
+    }
+
+    #[test]
+    fn internally_tagged() {
+        let schema = schema_for!(Tagged).to_value();
+        let tags: Vec<&Value> = alternatives(&schema).iter().map(|alternative| &alternative["properties"]["type"]["const"]).collect();
+        assert_eq!(tags, vec!["LinearKnob", "log", "Disabled"]);
+        assert_eq!(alternatives(&schema)[1]["$ref"], "#/$defs/LogarithmicKnob");
+
+        let config: Tagged = serde_json::from_value(json!({ "type": "log", "position": 8.0, "base": 2 })).unwrap();
+        assert_eq!(config.get_value(), 3.0);
+    }
+
+    #[test]
+    fn adjacently_tagged() {
+        let schema = schema_for!(Adjacent).to_value();
+        let alternatives = alternatives(&schema);
+        assert_eq!(alternatives[0]["properties"]["type"]["const"], "LinearKnob");
+        assert_eq!(alternatives[0]["properties"]["data"]["$ref"], "#/$defs/LinearKnob");
+    }
+}
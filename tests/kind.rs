@@ -0,0 +1,83 @@
+use enum_dispatch::enum_dispatch;
+
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+impl KnobControl for [u64; 16] {
+    fn get_value(&self) -> f64 {
+        self.iter().sum::<u64>() as f64
+    }
+}
+
+#[enum_dispatch(KnobControl, kind = KnobKind, box_above = 64)]
+#[derive(Debug, PartialEq)]
+pub enum AnyKnob {
+    LinearKnob,
+    LogarithmicKnob,
+    Stepped([u64; 16]),
+    #[enum_dispatch(fallback(default_value))]
+    Missing,
+}
+
+#[test]
+fn main() {
+    let knob = AnyKnob::from(LogarithmicKnob { position: 1. });
+    assert_eq!(knob.kind(), KnobKind::LogarithmicKnob);
+    assert_eq!(AnyKnob::Missing.kind(), KnobKind::Missing);
+
+    assert_eq!(
+        KnobKind::ALL,
+        [KnobKind::LinearKnob, KnobKind::LogarithmicKnob, KnobKind::Stepped, KnobKind::Missing]
+    );
+    assert!(KnobKind::LinearKnob < KnobKind::Missing);
+    let unique: HashSet<KnobKind> = KnobKind::ALL.iter().copied().collect();
+    assert_eq!(unique.len(), 4);
+
+    let mut counts = BTreeMap::new();
+    for knob in [AnyKnob::Missing, AnyKnob::from(LinearKnob::default()), AnyKnob::Missing] {
+        *counts.entry(knob.kind()).or_insert(0) += 1;
+    }
+    assert_eq!(counts.into_iter().collect::<Vec<_>>(), vec![(KnobKind::LinearKnob, 1), (KnobKind::Missing, 2)]);
+
+    assert_eq!(KnobKind::Stepped.to_string(), "Stepped");
+    assert_eq!(KnobKind::from_str("Stepped"), Ok(KnobKind::Stepped));
+    let error = "Linear".parse::<KnobKind>().unwrap_err();
+    assert_eq!(error.to_string(), "unknown KnobKind `Linear`");
+
+    assert_eq!(KnobKind::LinearKnob.default_value(), Some(AnyKnob::from(LinearKnob { position: 0. })));
+    assert_eq!(KnobKind::Stepped.default_value(), Some(AnyKnob::from([0; 16])));
+    assert_eq!(KnobKind::Stepped.default_value().unwrap().get_value(), 0.);
+    assert_eq!(KnobKind::Missing.default_value(), Some(AnyKnob::Missing));
+}
+
+#[test]
+fn default_value_without_default() {
+    assert_eq!(KnobKind::LogarithmicKnob.default_value(), None);
+}
@@ -0,0 +1,154 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn set_position(&mut self, value: f64);
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Slider {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+impl KnobControl for Slider {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position * 10.
+    }
+}
+
+// `Knob` itself doesn't implement `Describe`, so describing a `Control` must dispatch straight to
+// the knobs.
+#[enum_dispatch]
+#[derive(Debug, PartialEq)]
+enum Knob {
+    LinearKnob,
+    LogarithmicKnob,
+}
+
+#[enum_dispatch(KnobControl)]
+#[derive(Debug, PartialEq)]
+enum Control {
+    #[enum_dispatch(flatten)]
+    Knob,
+    Labeled {
+        label: &'static str,
+        #[dispatch]
+        slider: Slider,
+    },
+}
+
+#[enum_dispatch(Control)]
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for LinearKnob {
+    fn describe(&self) -> String {
+        format!("linear at {}", self.position)
+    }
+}
+
+impl Describe for LogarithmicKnob {
+    fn describe(&self) -> String {
+        format!("logarithmic at {}", self.position)
+    }
+}
+
+impl Describe for Slider {
+    fn describe(&self) -> String {
+        format!("slider at {}", self.position)
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+enum Panel {
+    #[enum_dispatch(flatten)]
+    Control,
+    Extra(LinearKnob),
+}
+
+// A plain struct sharing its name with the `Knob` enum is dispatched to like any other payload.
+mod plain {
+    pub struct Knob;
+
+    impl super::KnobControl for Knob {
+        fn set_position(&mut self, _value: f64) {}
+
+        fn get_value(&self) -> f64 {
+            -1.
+        }
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+enum Fixed {
+    Knob(plain::Knob),
+    Slider,
+}
+
+#[test]
+fn main() {
+    let mut control = Control::from(LinearKnob { position: 0.5 });
+    assert_eq!(control, Control::Knob(Knob::LinearKnob(LinearKnob { position: 0.5 })));
+    assert_eq!(control.get_value(), 0.5);
+    control.set_position(0.25);
+    assert_eq!(control.describe(), "linear at 0.25");
+
+    let control: Control = LogarithmicKnob { position: 1. }.into();
+    assert_eq!(control.get_value(), 1.);
+    assert_eq!(control.describe(), "logarithmic at 1");
+
+    let control = Control::Labeled { label: "volume", slider: Slider { position: 0.5 } };
+    assert_eq!(control.get_value(), 5.);
+    assert_eq!(control.describe(), "slider at 0.5");
+    assert!(matches!(control, Control::Labeled { label: "volume", .. }));
+
+    // `LinearKnob` is also a direct variant of `Panel`, so only `LogarithmicKnob` is converted
+    // through both levels.
+    let panel = Panel::from(LogarithmicKnob { position: 3. });
+    assert!(matches!(panel, Panel::Control(Control::Knob(Knob::LogarithmicKnob(_)))));
+    assert_eq!(panel.get_value(), 2.);
+    let panel = Panel::from(LinearKnob { position: 3. });
+    assert!(matches!(panel, Panel::Extra(_)));
+    assert_eq!(panel.get_value(), 3.);
+
+    let fixed = Fixed::from(plain::Knob);
+    assert_eq!(fixed.get_value(), -1.);
+    let fixed = Fixed::from(Slider { position: 0.5 });
+    assert_eq!(fixed.get_value(), 5.);
+}
@@ -0,0 +1,67 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Clone, Copy)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for f64 {
+    fn get_value(&self) -> f64 {
+        *self
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+enum Knob {
+    Primary(LinearKnob),
+    #[enum_dispatch(no_from)]
+    Secondary(LinearKnob),
+    #[enum_dispatch(no_from)]
+    Fixed(f64),
+}
+
+// Without any `From` impls, these can't conflict with user-written ones.
+#[enum_dispatch(KnobControl, no_from)]
+enum Manual {
+    Left(LinearKnob),
+    Right(LinearKnob),
+}
+
+impl From<LinearKnob> for Manual {
+    fn from(knob: LinearKnob) -> Self {
+        if knob.position < 0.5 {
+            Manual::Left(knob)
+        } else {
+            Manual::Right(knob)
+        }
+    }
+}
+
+impl From<f64> for Knob {
+    fn from(value: f64) -> Self {
+        Knob::Fixed(value.clamp(0., 1.))
+    }
+}
+
+#[test]
+fn main() {
+    let knob = LinearKnob { position: 0.75 };
+    assert!(matches!(Knob::from(knob), Knob::Primary(_)));
+    assert_eq!(Knob::Secondary(knob).get_value(), 0.75);
+    assert_eq!(Knob::from(2.).get_value(), 1.);
+
+    assert!(matches!(Manual::from(knob), Manual::Right(_)));
+    assert!(matches!(Manual::from(LinearKnob { position: 0.25 }), Manual::Left(_)));
+    assert_eq!(Manual::from(knob).get_value(), 0.75);
+}
@@ -0,0 +1,131 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+pub trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[enum_dispatch]
+pub trait Resizable {
+    fn resize(&mut self, factor: f64) -> f64;
+    fn reset(&mut self);
+    fn into_size(self) -> f64;
+}
+
+pub struct LinearKnob {
+    position: f64,
+    size: f64,
+}
+
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        self.position.log2()
+    }
+}
+
+impl Resizable for LinearKnob {
+    fn resize(&mut self, factor: f64) -> f64 {
+        self.size *= factor;
+        self.size
+    }
+
+    fn reset(&mut self) {
+        self.size = 1.0;
+    }
+
+    fn into_size(self) -> f64 {
+        self.size
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+#[enum_dispatch(Resizable, partial)]
+pub enum Knob {
+    #[enum_dispatch(implements(Resizable))]
+    LinearKnob,
+    LogarithmicKnob,
+}
+
+pub struct Wrapper<T>(T);
+
+impl<T: Resizable> Resizable for Wrapper<T> {
+    fn resize(&mut self, factor: f64) -> f64 {
+        self.0.resize(factor)
+    }
+
+    fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    fn into_size(self) -> f64 {
+        self.0.into_size()
+    }
+}
+
+#[enum_dispatch(Resizable, partial, no_from)]
+pub enum GenericKnob<T> {
+    #[enum_dispatch(implements(Resizable))]
+    Wrapped(Wrapper<T>),
+    Other(T),
+}
+
+#[test]
+fn dispatches_to_implementing_variants() {
+    let mut knob = Knob::from(LinearKnob { position: 0.5, size: 2.0 });
+    assert_eq!(knob.try_resize(3.0), Some(6.0));
+    assert_eq!(knob.try_reset(), Some(()));
+    assert_eq!(knob.get_value(), 0.5);
+    assert_eq!(knob.try_into_size(), Some(1.0));
+}
+
+#[test]
+fn returns_none_for_other_variants() {
+    let mut knob = Knob::from(LogarithmicKnob { position: 8.0 });
+    assert_eq!(knob.try_resize(3.0), None);
+    assert_eq!(knob.try_reset(), None);
+    assert_eq!(knob.get_value(), 3.0);
+    assert_eq!(knob.try_into_size(), None);
+}
+
+#[test]
+fn generic_payloads() {
+    let mut knob = GenericKnob::Wrapped(Wrapper(LinearKnob { position: 0.0, size: 1.0 }));
+    assert_eq!(knob.try_resize(4.0), Some(4.0));
+    let mut other = GenericKnob::Other(LinearKnob { position: 0.0, size: 1.0 });
+    assert_eq!(other.try_resize(4.0), None);
+}
+
+// The trait is registered after the enum, so the link is only fulfilled later.
+#[enum_dispatch(Describe, partial)]
+pub enum LateKnob {
+    LogarithmicKnob,
+    #[enum_dispatch(implements(Describe))]
+    LinearKnob,
+}
+
+#[enum_dispatch]
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for LinearKnob {
+    fn describe(&self) -> String {
+        format!("linear at {}", self.position)
+    }
+}
+
+#[test]
+fn deferred_links() {
+    assert_eq!(LateKnob::from(LinearKnob { position: 0.5, size: 1.0 }).try_describe(), Some("linear at 0.5".to_string()));
+    assert_eq!(LateKnob::from(LogarithmicKnob { position: 1.0 }).try_describe(), None);
+}
@@ -0,0 +1,96 @@
+use enum_dispatch::{enum_dispatch, remote_trait};
+
+use std::sync::Mutex;
+
+mod vendor {
+    pub struct Request(pub u32);
+
+    pub trait Handler {
+        fn handle(&self, request: &Request) -> String;
+        fn reset(&mut self);
+        fn finish(self) -> u32;
+    }
+}
+
+use vendor::Request;
+
+pub struct Echo {
+    handled: u32,
+}
+
+pub struct Archive([u32; 32]);
+
+impl vendor::Handler for Echo {
+    fn handle(&self, request: &Request) -> String {
+        format!("echo {}", request.0 + self.handled)
+    }
+
+    fn reset(&mut self) {
+        self.handled = 0;
+    }
+
+    fn finish(self) -> u32 {
+        self.handled
+    }
+}
+
+impl vendor::Handler for Archive {
+    fn handle(&self, request: &Request) -> String {
+        format!("archived {}", request.0 + self.0[0])
+    }
+
+    fn reset(&mut self) {
+        self.0 = [0; 32];
+    }
+
+    fn finish(self) -> u32 {
+        self.0.iter().sum()
+    }
+}
+
+// Linked before the remote trait is declared.
+#[enum_dispatch(crate::vendor::Handler, box_above = 64)]
+enum AnyHandler {
+    Echo,
+    Archive,
+}
+
+remote_trait! {
+    trait crate::vendor::Handler {
+        fn handle(&self, request: &crate::vendor::Request) -> String;
+        fn reset(&mut self);
+        fn finish(self) -> u32;
+    }
+}
+
+// Linked after the remote trait is declared.
+#[enum_dispatch(crate::vendor::Handler, dyn_conversions)]
+enum Shared {
+    Echo,
+    #[enum_dispatch(lock)]
+    Locked(Mutex<Echo>),
+}
+
+#[test]
+fn main() {
+    use vendor::Handler;
+
+    let mut echo = AnyHandler::from(Echo { handled: 1 });
+    assert_eq!(echo.handle(&Request(2)), "echo 3");
+    echo.reset();
+    assert_eq!(echo.handle(&Request(2)), "echo 2");
+
+    let mut archive = AnyHandler::from(Archive([1; 32]));
+    assert_eq!(archive.handle(&Request(2)), "archived 3");
+    assert_eq!(AnyHandler::from(Archive([1; 32])).finish(), 32);
+    archive.reset();
+    assert_eq!(archive.handle(&Request(2)), "archived 2");
+
+    let mut locked = Shared::from(Mutex::new(Echo { handled: 5 }));
+    assert_eq!(locked.handle(&Request(1)), "echo 6");
+    locked.reset();
+    assert_eq!(locked.finish(), 0);
+
+    let boxed: Box<dyn Handler> = Shared::from(Echo { handled: 4 }).into();
+    assert_eq!(boxed.handle(&Request(0)), "echo 4");
+}
@@ -17,7 +17,7 @@ mod scope1 {
 }
 
 mod scope2 {
-    use crate::scope1::{Item, Subitem1};
+    use crate::scope1::Item;
     use enum_dispatch::enum_dispatch;
     
     pub struct Subitem2;
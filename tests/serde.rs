@@ -68,10 +68,11 @@ fn through_serde() {
     ]);
 
     let deserialized_shapes: Vec<Shape> = serialized_shapes.iter().map(|s| {
-        serde_json::from_str(&s).unwrap()
+        serde_json::from_str(s).unwrap()
     }).collect();
 
     for (shape, new_shape) in shapes.iter().zip(deserialized_shapes.iter()) {
         assert_eq!(shape, new_shape);
+        assert_eq!(shape.area(), new_shape.area());
     }
 }
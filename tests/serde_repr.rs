@@ -0,0 +1,130 @@
+#![cfg(feature = "serde")]
+
+use enum_dispatch::enum_dispatch;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Sensor {
+    readings: [[u8; 32]; 2],
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        self.position.log2()
+    }
+}
+
+impl KnobControl for f64 {
+    fn get_value(&self) -> f64 {
+        *self
+    }
+}
+
+impl KnobControl for Sensor {
+    fn get_value(&self) -> f64 {
+        self.readings[0][0] as f64
+    }
+}
+
+#[enum_dispatch(KnobControl, serde(transparent))]
+#[derive(Debug, PartialEq)]
+pub enum Transparent {
+    LinearKnob,
+    Fixed(f64),
+}
+
+#[enum_dispatch(KnobControl, serde(tag = "type"))]
+#[derive(Debug, PartialEq)]
+pub enum Tagged {
+    LinearKnob,
+    #[enum_dispatch(rename = "log")]
+    LogarithmicKnob,
+    #[enum_dispatch(fallback(default_value))]
+    Disabled,
+}
+
+#[enum_dispatch(KnobControl, serde(tag = "type", content = "data"), box_above = 32)]
+#[derive(Debug, PartialEq)]
+pub enum Adjacent {
+    LinearKnob,
+    Sensor,
+}
+
+#[enum_dispatch(serde(tag = "kind"), no_from)]
+#[derive(Debug, PartialEq)]
+pub enum AnyGeneric<K> {
+    Knob(K),
+    LinearKnob,
+}
+
+#[test]
+fn transparent() {
+    let knob: Transparent = LinearKnob { position: 0.5 }.into();
+    assert_eq!(serde_json::to_value(&knob).unwrap(), json!({ "position": 0.5 }));
+    assert_eq!(serde_json::to_value(Transparent::from(2.0)).unwrap(), json!(2.0));
+
+    let fixed: Transparent = serde_json::from_value(json!(3.0)).unwrap();
+    assert_eq!(fixed.get_value(), 3.0);
+    let knob: Transparent = serde_json::from_value(json!({ "position": 0.25 })).unwrap();
+    assert_eq!(knob, LinearKnob { position: 0.25 }.into());
+}
+
+#[test]
+fn internally_tagged() {
+    let knob: Tagged = LinearKnob { position: 0.5 }.into();
+    assert_eq!(serde_json::to_value(&knob).unwrap(), json!({ "type": "LinearKnob", "position": 0.5 }));
+    let log: Tagged = LogarithmicKnob { position: 8.0 }.into();
+    assert_eq!(serde_json::to_value(&log).unwrap(), json!({ "type": "log", "position": 8.0 }));
+    assert_eq!(serde_json::to_value(Tagged::Disabled).unwrap(), json!({ "type": "Disabled" }));
+
+    let configs: Vec<Tagged> = serde_json::from_str(
+        r#"[{ "type": "log", "position": 4.0 }, { "type": "LinearKnob", "position": 1.5 }, { "type": "Disabled" }]"#,
+    )
+    .unwrap();
+    let values: Vec<f64> = configs.iter().map(KnobControl::get_value).collect();
+    assert_eq!(values, vec![2.0, 1.5, 0.0]);
+    assert!(serde_json::from_str::<Tagged>(r#"{ "type": "LogarithmicKnob", "position": 4.0 }"#).is_err());
+}
+
+#[test]
+fn adjacently_tagged() {
+    let knob: Adjacent = LinearKnob { position: 0.5 }.into();
+    assert_eq!(serde_json::to_value(&knob).unwrap(), json!({ "type": "LinearKnob", "data": { "position": 0.5 } }));
+
+    let sensor: Adjacent = Sensor { readings: [[7; 32]; 2] }.into();
+    let serialized = serde_json::to_string(&sensor).unwrap();
+    let deserialized: Adjacent = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, sensor);
+    assert_eq!(deserialized.get_value(), 7.0);
+}
+
+#[test]
+fn generic_payloads() {
+    let linear: AnyGeneric<LogarithmicKnob> = AnyGeneric::LinearKnob(LinearKnob { position: 2.0 });
+    assert_eq!(serde_json::to_value(&linear).unwrap(), json!({ "kind": "LinearKnob", "position": 2.0 }));
+    let log: AnyGeneric<LogarithmicKnob> = serde_json::from_value(json!({ "kind": "Knob", "position": 16.0 })).unwrap();
+    assert_eq!(log, AnyGeneric::Knob(LogarithmicKnob { position: 16.0 }));
+}
@@ -0,0 +1,78 @@
+use enum_dispatch::enum_dispatch;
+
+use std::rc::Rc;
+
+#[enum_dispatch]
+pub trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+pub struct LinearKnob {
+    name: String,
+    position: f64,
+}
+
+pub struct LogarithmicKnob {
+    name: String,
+    position: f64,
+    history: [f64; 8],
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        self.position.log2() + self.history[0]
+    }
+}
+
+#[enum_dispatch(KnobControl, fields(name: String, position: f64), box_above = 48)]
+pub enum Knob {
+    LinearKnob,
+    LogarithmicKnob,
+    Wrapped(Box<LinearKnob>),
+}
+
+// The `Rc` payload can't be borrowed mutably, so the field only gets `name`.
+#[enum_dispatch(fields(ref name: String))]
+pub enum SharedKnob {
+    Linear(Rc<LinearKnob>),
+    LogarithmicKnob,
+}
+
+fn knobs() -> Vec<Knob> {
+    vec![
+        Knob::from(LinearKnob { name: "volume".to_string(), position: 0.5 }),
+        Knob::from(LogarithmicKnob { name: "gain".to_string(), position: 8.0, history: [0.0; 8] }),
+        Knob::from(Box::new(LinearKnob { name: "pan".to_string(), position: 0.0 })),
+    ]
+}
+
+#[test]
+fn shared_accessors() {
+    let knobs = knobs();
+    let names: Vec<&str> = knobs.iter().map(|knob| knob.name().as_str()).collect();
+    assert_eq!(names, vec!["volume", "gain", "pan"]);
+    assert_eq!(*knobs[1].position(), 8.0);
+}
+
+#[test]
+fn mutable_accessors() {
+    let mut knobs = knobs();
+    for knob in knobs.iter_mut() {
+        knob.name_mut().push_str("_knob");
+        *knob.position_mut() = 4.0;
+    }
+    assert_eq!(knobs[2].name(), "pan_knob");
+    assert_eq!(knobs.iter().map(|knob| knob.get_value()).collect::<Vec<_>>(), vec![4.0, 2.0, 4.0]);
+}
+
+#[test]
+fn smart_pointer_payloads() {
+    let knob = SharedKnob::from(Rc::new(LinearKnob { name: "shared".to_string(), position: 0.0 }));
+    assert_eq!(knob.name(), "shared");
+}
@@ -0,0 +1,204 @@
+use enum_dispatch::enum_dispatch;
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+#[derive(Debug)]
+pub struct NotFound(String);
+
+#[derive(Debug)]
+pub struct Wrapped(NotFound);
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} not found", self.0)
+    }
+}
+
+impl fmt::Display for Wrapped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wrapped")
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+impl std::error::Error for Wrapped {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[enum_dispatch(fmt::Display, fmt::Debug, error::Error)]
+pub enum AnyError {
+    NotFound,
+    Wrapped,
+}
+
+#[enum_dispatch(iter::Iterator, iter::DoubleEndedIterator, iter::ExactSizeIterator)]
+pub enum Counter {
+    Range(Range<u32>),
+    Rev(std::iter::Rev<Range<u32>>),
+}
+
+#[enum_dispatch(io::Read, io::BufRead, io::Seek)]
+pub enum Source {
+    Cursor(Cursor<Vec<u8>>),
+    Empty(io::Empty),
+}
+
+#[enum_dispatch(io::Write)]
+pub enum Sink {
+    Vec(Vec<u8>),
+    Discard(io::Sink),
+}
+
+#[enum_dispatch(fmt::Write)]
+pub enum Text {
+    String,
+}
+
+#[enum_dispatch(hash::Hash)]
+pub enum Key {
+    Name(String),
+    Id(u64),
+}
+
+#[enum_dispatch(convert::AsRef, convert::AsMut)]
+pub enum Bytes {
+    Vec(Vec<u8>),
+    Array([u8; 4]),
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn display_and_debug_are_transparent() {
+    let error: AnyError = NotFound("config".to_string()).into();
+    assert_eq!(error.to_string(), "config not found");
+    assert_eq!(format!("{:?}", error), "NotFound(\"config\")");
+}
+
+#[test]
+fn error_forwards_source() {
+    use std::error::Error;
+
+    let plain: AnyError = NotFound("a".to_string()).into();
+    assert!(plain.source().is_none());
+
+    let wrapped: AnyError = Wrapped(NotFound("b".to_string())).into();
+    assert_eq!(wrapped.source().unwrap().to_string(), "b not found");
+
+    let boxed: Box<dyn Error> = wrapped.into();
+    assert_eq!(boxed.to_string(), "wrapped");
+}
+
+#[test]
+fn iterators_dispatch() {
+    let forward: Counter = (0..4).into();
+    assert_eq!(forward.len(), 4);
+    assert_eq!(forward.collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+    let mut backward: Counter = (0..4).rev().into();
+    assert_eq!(backward.next_back(), Some(0));
+    assert_eq!(backward.size_hint(), (3, Some(3)));
+    assert_eq!(backward.map(|n| n * 10).collect::<Vec<_>>(), vec![30, 20, 10]);
+}
+
+#[test]
+fn readers_dispatch() {
+    let mut source: Source = Cursor::new(b"first\nsecond\n".to_vec()).into();
+    let mut line = String::new();
+    source.read_line(&mut line).unwrap();
+    assert_eq!(line, "first\n");
+    source.seek(SeekFrom::Start(1)).unwrap();
+    let mut rest = String::new();
+    source.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "irst\nsecond\n");
+
+    let mut empty: Source = io::empty().into();
+    assert_eq!(empty.fill_buf().unwrap(), b"");
+    assert_eq!(empty.read(&mut [0; 4]).unwrap(), 0);
+}
+
+#[test]
+fn writers_dispatch() {
+    let mut sink: Sink = Vec::new().into();
+    write!(sink, "{}-{}", 1, 2).unwrap();
+    sink.flush().unwrap();
+    match sink {
+        Sink::Vec(bytes) => assert_eq!(bytes, b"1-2"),
+        Sink::Discard(_) => unreachable!(),
+    }
+
+    let mut discard: Sink = io::sink().into();
+    assert_eq!(discard.write(b"ignored").unwrap(), 7);
+
+    let mut text: Text = String::new().into();
+    fmt::Write::write_fmt(&mut text, format_args!("{}!", "hi")).unwrap();
+    let Text::String(text) = text;
+    assert_eq!(text, "hi!");
+}
+
+#[test]
+fn hash_is_transparent() {
+    let name: Key = "id".to_string().into();
+    let id: Key = 7u64.into();
+    assert_eq!(hash_of(&name), hash_of(&"id".to_string()));
+    assert_eq!(hash_of(&id), hash_of(&7u64));
+}
+
+#[test]
+fn as_ref_and_as_mut_dispatch() {
+    let mut array: Bytes = [1, 2, 3, 4].into();
+    array.as_mut()[0] = 9;
+    assert_eq!(array.as_ref(), &[9, 2, 3, 4]);
+
+    let vec: Bytes = vec![5, 6].into();
+    assert_eq!(vec.as_ref().len(), 2);
+}
+
+mod local_read {
+    use super::*;
+
+    // Linked before the local trait below is registered, so a bare `Read` must not be taken for
+    // `std::io::Read`.
+    #[enum_dispatch(Read)]
+    pub enum Device {
+        Keyboard,
+        Mouse,
+    }
+
+    #[enum_dispatch]
+    pub trait Read {
+        fn read(&self) -> u8;
+    }
+
+    pub struct Keyboard;
+    pub struct Mouse;
+
+    impl Read for Keyboard {
+        fn read(&self) -> u8 {
+            b'k'
+        }
+    }
+
+    impl Read for Mouse {
+        fn read(&self) -> u8 {
+            b'm'
+        }
+    }
+
+    #[test]
+    fn local_traits_shadow_catalog_names() {
+        let devices: Vec<Device> = vec![Keyboard.into(), Mouse.into()];
+        assert_eq!(devices.iter().map(Read::read).collect::<Vec<_>>(), b"km");
+    }
+}
@@ -0,0 +1,95 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn set_position(&mut self, value: f64);
+    fn get_value(&self) -> f64;
+}
+
+#[enum_dispatch(Knob)]
+trait Described {
+    fn describe(&self) -> String;
+}
+
+#[derive(Default)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+impl Described for LinearKnob {
+    fn describe(&self) -> String {
+        format!("linear at {}", self.position)
+    }
+}
+
+impl Described for LogarithmicKnob {
+    fn describe(&self) -> String {
+        format!("logarithmic at {}", self.position)
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+#[enum_dispatch]
+enum Knob {
+    Linear {
+        id: u32,
+        #[dispatch]
+        knob: LinearKnob,
+    },
+    #[enum_dispatch(from_default)]
+    Labeled(String, #[dispatch] LinearKnob),
+    Logarithmic { knob: LogarithmicKnob },
+}
+
+#[test]
+fn struct_variants() {
+    let mut linear = Knob::Linear {
+        id: 7,
+        knob: LinearKnob { position: 0. },
+    };
+    let mut labeled = Knob::from(LinearKnob::default());
+    let mut logarithmic = Knob::from(LogarithmicKnob { position: 0. });
+
+    linear.set_position(0.25);
+    labeled.set_position(0.5);
+    logarithmic.set_position(1.);
+
+    assert_eq!(linear.get_value(), 0.25);
+    assert_eq!(labeled.get_value(), 0.5);
+    assert_eq!(logarithmic.get_value(), 1.);
+
+    assert_eq!(linear.describe(), "linear at 0.25");
+    assert_eq!(logarithmic.describe(), "logarithmic at 1");
+
+    match (linear, labeled) {
+        (Knob::Linear { id, .. }, Knob::Labeled(label, _)) => {
+            assert_eq!(id, 7);
+            assert_eq!(label, "");
+        }
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,90 @@
+use enum_dispatch::enum_dispatch;
+
+use std::convert::TryFrom;
+
+#[enum_dispatch]
+pub trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+pub struct LinearKnob {
+    position: f64,
+}
+
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+pub struct SteppedKnob {
+    steps: [u32; 16],
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        self.position.log2()
+    }
+}
+
+impl KnobControl for SteppedKnob {
+    fn get_value(&self) -> f64 {
+        self.steps.iter().sum::<u32>() as f64
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+pub enum BasicKnob {
+    LinearKnob,
+    #[enum_dispatch(fallback(default_value))]
+    Disabled,
+}
+
+#[enum_dispatch(KnobControl, superset_of = BasicKnob)]
+pub enum Knob {
+    LogarithmicKnob,
+    #[enum_dispatch(fallback(default_value))]
+    Disabled,
+    Linear(LinearKnob),
+}
+
+#[enum_dispatch(KnobControl, box_above = 32)]
+pub enum SteppedOnly {
+    SteppedKnob,
+}
+
+#[enum_dispatch(KnobControl, box_above = 16, superset_of = SteppedOnly)]
+pub enum AnyKnob {
+    LinearKnob,
+    SteppedKnob,
+}
+
+#[test]
+fn into_superset() {
+    let knob = Knob::from(BasicKnob::from(LinearKnob { position: 0.5 }));
+    assert!(matches!(knob, Knob::Linear(_)));
+    assert_eq!(knob.get_value(), 0.5);
+    assert!(matches!(Knob::from(BasicKnob::Disabled), Knob::Disabled));
+}
+
+#[test]
+fn into_subset() {
+    let basic = BasicKnob::try_from(Knob::from(LinearKnob { position: 0.25 })).ok().unwrap();
+    assert_eq!(basic.get_value(), 0.25);
+    assert!(matches!(BasicKnob::try_from(Knob::Disabled), Ok(BasicKnob::Disabled)));
+
+    let rejected = BasicKnob::try_from(Knob::from(LogarithmicKnob { position: 8.0 })).err().unwrap();
+    assert_eq!(rejected.get_value(), 3.0);
+}
+
+#[test]
+fn boxed_payloads() {
+    let knob = AnyKnob::from(SteppedOnly::from(SteppedKnob { steps: [1; 16] }));
+    assert_eq!(knob.get_value(), 16.0);
+    assert_eq!(SteppedOnly::try_from(knob).ok().unwrap().get_value(), 16.0);
+    assert!(SteppedOnly::try_from(AnyKnob::from(LinearKnob { position: 1.0 })).is_err());
+}
@@ -0,0 +1,107 @@
+use enum_dispatch::{enum_dispatch, enum_dispatch_enum};
+
+#[enum_dispatch]
+trait Shaped {
+    fn area(&self) -> f32;
+}
+
+mod shapes {
+    pub struct Circle {
+        pub r: f32,
+    }
+
+    pub struct Square {
+        pub s: f32,
+    }
+}
+
+impl Shaped for shapes::Circle {
+    fn area(&self) -> f32 {
+        self.r * self.r * std::f32::consts::PI
+    }
+}
+
+impl Shaped for shapes::Square {
+    fn area(&self) -> f32 {
+        self.s * self.s
+    }
+}
+
+pub struct Wrapper<T>(T);
+
+impl<T: Into<f32> + Copy> Shaped for Wrapper<T> {
+    fn area(&self) -> f32 {
+        self.0.into()
+    }
+}
+
+impl Shaped for Vec<shapes::Square> {
+    fn area(&self) -> f32 {
+        self.iter().map(Shaped::area).sum()
+    }
+}
+
+impl Shaped for &'static str {
+    fn area(&self) -> f32 {
+        self.len() as f32
+    }
+}
+
+enum_dispatch_enum! {
+    #[enum_dispatch(Shaped)]
+    enum Shape {
+        shapes::Circle,
+        Wrapper<u8>,
+        Wrapper<u16>,
+        Vec<shapes::Square>,
+        &'static str,
+        Single(shapes::Square),
+    }
+}
+
+enum_dispatch_enum! {
+    enum Unattributed {
+        shapes::Square,
+        Wrapper<u8>,
+    }
+}
+
+#[enum_dispatch(Unattributed)]
+trait Perimeter {
+    fn perimeter(&self) -> f32 {
+        0.
+    }
+}
+
+impl Perimeter for shapes::Square {
+    fn perimeter(&self) -> f32 {
+        self.s * 4.
+    }
+}
+
+impl Perimeter for Wrapper<u8> {}
+
+#[test]
+fn derived_names() {
+    let shapes = [
+        Shape::Circle(shapes::Circle { r: 1. }),
+        Shape::WrapperU8(Wrapper(2)),
+        Shape::WrapperU16(Wrapper(3)),
+        Shape::VecSquare(vec![shapes::Square { s: 1. }, shapes::Square { s: 2. }]),
+        Shape::RefStr("four"),
+        Shape::Single(shapes::Square { s: 3. }),
+    ];
+    let areas: Vec<f32> = shapes.iter().map(Shaped::area).collect();
+    assert_eq!(areas, vec![std::f32::consts::PI, 2., 3., 5., 4., 9.]);
+
+    let shape: Shape = Wrapper(7u16).into();
+    assert_eq!(shape.area(), 7.);
+}
+
+#[test]
+fn unattributed() {
+    let square = Unattributed::from(shapes::Square { s: 2. });
+    let wrapper = Unattributed::WrapperU8(Wrapper(1));
+    assert_eq!(square.perimeter(), 8.);
+    assert_eq!(wrapper.perimeter(), 0.);
+}
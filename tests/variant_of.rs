@@ -0,0 +1,68 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+// Payloads don't need to be `'static`.
+impl KnobControl for &f64 {
+    fn get_value(&self) -> f64 {
+        **self
+    }
+}
+
+#[enum_dispatch(variant_trait = KnobVariant)]
+#[derive(Debug, PartialEq)]
+enum Knob<'a> {
+    LinearKnob,
+    LogarithmicKnob,
+    Borrowed(&'a f64),
+}
+
+/// Generic code can access any variant type by naming the generated trait.
+fn position_of<'a, T: KnobVariant<'a> + KnobControl>(knob: &Knob<'a>) -> Option<f64> {
+    knob.get::<T>().map(T::get_value)
+}
+
+#[test]
+fn main() {
+    let mut knob = Knob::from(LinearKnob { position: 0.5 });
+    assert!(knob.contains_type::<LinearKnob>());
+    assert!(!knob.contains_type::<LogarithmicKnob>());
+    assert_eq!(knob.get::<LinearKnob>(), Some(&LinearKnob { position: 0.5 }));
+    assert_eq!(knob.get::<LogarithmicKnob>(), None);
+    knob.get_mut::<LinearKnob>().unwrap().position = 3.;
+    assert_eq!(position_of::<LinearKnob>(&knob), Some(3.));
+    assert_eq!(position_of::<LogarithmicKnob>(&knob), None);
+
+    let knob = knob.try_into_variant::<LogarithmicKnob>().unwrap_err();
+    assert_eq!(knob.try_into_variant::<LinearKnob>(), Ok(LinearKnob { position: 3. }));
+
+    let value = 0.25;
+    let knob = Knob::from(&value);
+    assert_eq!(knob.get::<&f64>(), Some(&&0.25));
+    assert_eq!(position_of::<&f64>(&knob), Some(0.25));
+}
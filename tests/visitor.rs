@@ -0,0 +1,117 @@
+use enum_dispatch::enum_dispatch;
+
+#[enum_dispatch]
+trait KnobControl {
+    fn set_position(&mut self, value: f64);
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Debug, Default)]
+pub struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct LogarithmicKnob {
+    position: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Bank {
+    positions: [f64; 16],
+}
+
+impl KnobControl for LinearKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+impl KnobControl for Bank {
+    fn set_position(&mut self, value: f64) {
+        self.positions.iter_mut().for_each(|position| *position = value);
+    }
+
+    fn get_value(&self) -> f64 {
+        self.positions.iter().sum()
+    }
+}
+
+#[enum_dispatch(KnobControl, box_above = 64, visitor = AnyKnobVisitor)]
+#[derive(Debug)]
+enum AnyKnob {
+    LinearKnob,
+    LogarithmicKnob,
+    Bank,
+    #[enum_dispatch(fallback(default_value))]
+    Disconnected,
+}
+
+struct Describe;
+
+impl AnyKnobVisitor<String> for Describe {
+    fn visit<T: KnobControl>(self, knob: &T) -> String {
+        let name = std::any::type_name::<T>().rsplit("::").next().unwrap();
+        format!("{} = {}", name, knob.get_value())
+    }
+}
+
+struct Reset(f64);
+
+impl AnyKnobVisitorMut<()> for Reset {
+    fn visit_mut<T: KnobControl>(self, knob: &mut T) {
+        knob.set_position(self.0);
+    }
+}
+
+struct Consume;
+
+impl AnyKnobVisitorOwned<f64> for Consume {
+    fn visit_owned<T: KnobControl>(self, knob: T) -> f64 {
+        knob.get_value()
+    }
+}
+
+#[test]
+fn main() {
+    let mut knobs = [
+        AnyKnob::from(LinearKnob { position: 0.5 }),
+        AnyKnob::from(LogarithmicKnob { position: 1. }),
+        AnyKnob::from(Bank::default()),
+        AnyKnob::Disconnected,
+    ];
+    let described: Vec<String> = knobs.iter().map(|knob| knob.accept(Describe)).collect();
+    assert_eq!(described, ["LinearKnob = 0.5", "LogarithmicKnob = 1", "Bank = 0", "AnyKnob = 0"]);
+
+    knobs.iter_mut().for_each(|knob| knob.accept_mut(Reset(0.25)));
+    assert_eq!(knobs[2].get_value(), 4.);
+
+    let [linear, _, bank, disconnected] = knobs;
+    assert_eq!(linear.accept_owned(Consume), 0.25);
+    assert_eq!(bank.accept_owned(Consume), 4.);
+    assert_eq!(disconnected.accept_owned(Consume), 0.);
+
+    // The body is expanded separately for each variant, so it isn't limited to `KnobControl`.
+    let knob = AnyKnob::from(LogarithmicKnob { position: 3. });
+    assert_eq!(any_knob_match!(&knob, inner => format!("{:?}", inner)), "LogarithmicKnob { position: 3.0 }");
+    // Boxed payloads are unboxed, whether the enum is matched by value or by reference.
+    let bank = AnyKnob::from(Bank::default());
+    assert_eq!(any_knob_match!(&bank, inner => std::mem::size_of_val(inner)), 128);
+    let mut knob = AnyKnob::from(LinearKnob::default());
+    any_knob_match!(&mut knob, inner => inner.set_position(0.75));
+    assert_eq!(any_knob_match!(knob, inner => inner.get_value()), 0.75);
+}
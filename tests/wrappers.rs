@@ -0,0 +1,165 @@
+use enum_dispatch::enum_dispatch;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+#[enum_dispatch]
+trait Counter {
+    fn value(&self) -> u32;
+    fn increment(&mut self);
+}
+
+#[enum_dispatch]
+trait Consume {
+    fn consume(self) -> u32;
+}
+
+#[derive(Default)]
+pub struct SimpleCounter {
+    count: u32,
+}
+
+impl Counter for SimpleCounter {
+    fn value(&self) -> u32 {
+        self.count
+    }
+
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+impl Consume for SimpleCounter {
+    fn consume(self) -> u32 {
+        self.count
+    }
+}
+
+pub struct DoubleCounter {
+    count: u32,
+}
+
+impl Counter for DoubleCounter {
+    fn value(&self) -> u32 {
+        self.count * 2
+    }
+
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+#[enum_dispatch(Counter)]
+enum AnyCounter {
+    Boxed(Box<SimpleCounter>),
+    #[enum_dispatch(lock)]
+    Shared(Rc<RefCell<SimpleCounter>>),
+    #[enum_dispatch(lock)]
+    Locked(Arc<Mutex<DoubleCounter>>),
+    #[enum_dispatch(lock = ignore_poison)]
+    ReadWrite(Arc<RwLock<u64>>),
+    Borrowed(&'static mut SimpleCounter),
+}
+
+impl Counter for u64 {
+    fn value(&self) -> u32 {
+        *self as u32
+    }
+
+    fn increment(&mut self) {
+        *self += 10;
+    }
+}
+
+#[enum_dispatch(Consume)]
+enum AnyConsume {
+    Boxed(Box<SimpleCounter>),
+    #[enum_dispatch(lock)]
+    Locked(Mutex<u8>),
+}
+
+impl Consume for u8 {
+    fn consume(self) -> u32 {
+        self as u32 + 100
+    }
+}
+
+/// A counter shared between several owners, which counts increments from all of them.
+pub struct SharedCounter(Rc<RefCell<u32>>);
+
+impl Counter for RefCell<DoubleCounter> {
+    fn value(&self) -> u32 {
+        self.borrow().value() + 1000
+    }
+
+    fn increment(&mut self) {
+        self.get_mut().increment();
+    }
+}
+
+impl Counter for Rc<SharedCounter> {
+    fn value(&self) -> u32 {
+        *self.0.borrow()
+    }
+
+    fn increment(&mut self) {
+        *self.0.borrow_mut() += 1;
+    }
+}
+
+#[enum_dispatch(Counter)]
+enum WrapperCounter {
+    Cell(RefCell<DoubleCounter>),
+    #[enum_dispatch(no_unwrap)]
+    Shared(Rc<SharedCounter>),
+}
+
+#[test]
+fn dispatch_through_wrappers() {
+    let shared = Rc::new(RefCell::new(SimpleCounter::default()));
+    let locked = Arc::new(Mutex::new(DoubleCounter { count: 1 }));
+    let borrowed: &'static mut SimpleCounter = Box::leak(Box::default());
+
+    let mut counters = [
+        AnyCounter::from(Box::new(SimpleCounter { count: 5 })),
+        AnyCounter::from(shared.clone()),
+        AnyCounter::from(locked.clone()),
+        AnyCounter::from(0u64),
+        AnyCounter::from(borrowed),
+    ];
+    for counter in counters.iter_mut() {
+        counter.increment();
+    }
+    let values: Vec<u32> = counters.iter().map(Counter::value).collect();
+    assert_eq!(values, vec![6, 1, 4, 10, 1]);
+
+    // The wrapped values are shared with the enum.
+    assert_eq!(shared.borrow().count, 1);
+    assert_eq!(locked.lock().unwrap().count, 2);
+}
+
+#[test]
+fn consume_through_wrappers() {
+    let boxed = AnyConsume::from(Box::new(SimpleCounter { count: 3 }));
+    let locked = AnyConsume::from(Mutex::new(7u8));
+    let unwrapped = AnyConsume::from(9u8);
+    assert_eq!(boxed.consume(), 3);
+    assert_eq!(locked.consume(), 107);
+    assert_eq!(unwrapped.consume(), 109);
+}
+
+#[test]
+fn dispatch_to_wrappers() {
+    let count = Rc::new(RefCell::new(0));
+    let mut counters = [
+        WrapperCounter::from(RefCell::new(DoubleCounter { count: 1 })),
+        WrapperCounter::from(Rc::new(SharedCounter(count.clone()))),
+    ];
+    for counter in counters.iter_mut() {
+        counter.increment();
+    }
+    let values: Vec<u32> = counters.iter().map(Counter::value).collect();
+    assert_eq!(values, vec![1004, 1]);
+    assert_eq!(*count.borrow(), 1);
+}